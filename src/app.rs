@@ -1,42 +1,185 @@
-use crate::{renderer::Renderer, video_player::VideoPlayer};
+use crate::cli::Args;
+use rs_wgpu_video_player::{
+    renderer::{CapturedImage, Renderer, ToneMapping},
+    video_player::{
+        write_png, AudioOutput, Frame, FrameQueue, LoopMode, PlaybackClock, PlaybackParams,
+        PlayerError, PlayerEvent, Reconnect, Rotation, SeekMode, VideoPlayer,
+    },
+};
 use std::{
     error::Error,
+    path::Path,
+    sync::Arc,
+    thread,
     time::{Duration, Instant},
 };
+use tokio::sync::broadcast::{
+    self,
+    error::{RecvError, TryRecvError},
+};
 use winit::{
     dpi::LogicalSize,
-    event::{Event, KeyEvent, WindowEvent},
+    event::{ElementState, Event, KeyEvent, StartCause, WindowEvent},
     event_loop::{ControlFlow, EventLoop},
     keyboard::{Key, NamedKey},
-    window::WindowBuilder,
+    window::{Fullscreen, WindowBuilder},
 };
 
-pub struct App {}
+const WINDOW_TITLE: &str = "WGPU Video Player";
+
+/// How far the arrow keys jump.
+const SEEK_STEP_US: u64 = 10_000_000;
+
+/// Speeds the `[` / `]` keys step through.
+const RATE_STEPS: [f64; 8] = [0.25, 0.5, 0.75, 1.0, 1.25, 1.5, 2.0, 4.0];
+
+/// Operators the `T` key cycles through for HDR content.
+const TONE_MAPPINGS: [ToneMapping; 3] = [
+    ToneMapping::Bt2390,
+    ToneMapping::Hable,
+    ToneMapping::Reinhard,
+];
+
+/// Redraw rate while paused, so seeks still show up without busy-looping.
+const PAUSED_REDRAW_INTERVAL: Duration = Duration::from_millis(30);
+
+/// Longest sleep while holding a headless frame until it is due.
+const MAX_DUE_WAIT: Duration = Duration::from_millis(10);
+
+pub struct App {
+    args: Args,
+}
 
 impl App {
-    pub async fn run(&self, url: String) -> Result<(), Box<dyn Error>> {
+    pub fn new(args: Args) -> Self {
+        Self { args }
+    }
+
+    pub async fn run(&self) -> Result<(), Box<dyn Error>> {
         let mut video_player = VideoPlayer::new();
+        // Subscribe first so `Opened` isn't missed.
+        let events = video_player.events();
+        video_player.set_rate(self.args.speed);
         video_player
-            .start_playback(
-                &url,
-                Default::default(),
-            )
+            .start_playback(&self.args.input, self.playback_params())
             .await?;
 
+        if self.args.headless {
+            self.run_headless(video_player, events).await
+        } else {
+            self.run_windowed(video_player, events).await
+        }
+    }
+
+    fn playback_params(&self) -> PlaybackParams {
+        let args = &self.args;
+        PlaybackParams {
+            pixel_format: args.pixel_format,
+            is_live: args.live || args.no_pacing,
+            // Audio would pace decoding through the clock
+            audio: match args.no_pacing {
+                true => AudioOutput::Disabled,
+                false => AudioOutput::Device,
+            },
+            start_us: args.start.unwrap_or(0),
+            loop_mode: self.loop_mode(),
+            frame_queue: match args.queue_depth {
+                Some(depth) => FrameQueue::Bounded(depth.get()),
+                None => FrameQueue::Latest,
+            },
+            frame_pool_size: None,
+            output_size: args.output_size,
+            scale_filter: args.scale_filter,
+            read_timeout: (!args.read_timeout.is_zero()).then_some(args.read_timeout),
+            reconnect: Reconnect::attempts(args.reconnect_attempts),
+        }
+    }
+
+    fn loop_mode(&self) -> LoopMode {
+        match self.args.looping {
+            true => LoopMode::Whole,
+            false => LoopMode::Off,
+        }
+    }
+
+    /// Draw every frame the decoder shows into an offscreen target until the
+    /// input ends or `--frames` have been presented.
+    async fn run_headless(
+        &self,
+        mut video_player: VideoPlayer,
+        mut events: broadcast::Receiver<PlayerEvent>,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut renderer: Option<Renderer> = None;
+        let mut presented: u64 = 0;
+        let clock = video_player.clock();
+
+        while self.args.frames.is_none_or(|frames| presented < frames) {
+            tokio::select! {
+                frame = video_player.next_frame() => {
+                    let Some(frame) = frame else { continue };
+                    // A bounded queue is filled ahead of the clock
+                    if !self.args.no_pacing {
+                        wait_until_due(&clock, frame.ts_us).await;
+                    }
+                    let renderer = match renderer.as_mut() {
+                        Some(renderer) => renderer,
+                        None => {
+                            let (width, height) = frame.display_size();
+                            renderer.insert(Renderer::new_headless(width, height).await?)
+                        }
+                    };
+                    // Follows mid-stream size changes
+                    let (width, height) = frame.display_size();
+                    renderer.set_video_size(width, height);
+                    renderer.set_frame(&frame);
+                    renderer.render()?;
+                    presented += 1;
+                }
+                event = events.recv() => match event {
+                    Ok(PlayerEvent::EndOfStream) | Err(RecvError::Closed) => break,
+                    Ok(PlayerEvent::Error(e)) => return Err(e.into()),
+                    _ => {}
+                },
+            }
+        }
+
+        video_player.stop_playback();
+        print_stats(&video_player);
+        Ok(())
+    }
+
+    async fn run_windowed(
+        &self,
+        mut video_player: VideoPlayer,
+        mut events: broadcast::Receiver<PlayerEvent>,
+    ) -> Result<(), Box<dyn Error>> {
+        let args = &self.args;
         let event_loop = EventLoop::new()?;
 
+        let (width, height) = args.window_size.unwrap_or((1270, 720));
         let window = WindowBuilder::new()
-            .with_title("WGPU Video Player")
-            .with_inner_size(LogicalSize::new(1270, 720))
+            .with_title(WINDOW_TITLE)
+            .with_inner_size(LogicalSize::new(width, height))
             .with_resizable(false)
+            .with_fullscreen(args.fullscreen.then_some(Fullscreen::Borderless(None)))
             .build(&event_loop)?;
 
         let mut renderer = Renderer::new(&window).await?;
 
+        let clock = video_player.clock();
         let mut last_ts_us: u64 = 0;
-        let mut base_ts_us: Option<u64> = None;
-        let mut wall_start: Option<Instant> = None;
-        let mut first_frame_size: Option<(u32, u32)> = None;
+        // Shown in the title while not simply playing.
+        let mut status: Option<&str> = Some("opening");
+        let mut video_size: Option<(u32, u32)> = None;
+        // Kept for `S` snapshots at native resolution
+        let mut shown_frame: Option<Arc<Frame>> = None;
+        let snapshot_name = snapshot_basename(&args.input);
+        // `L` toggles looping; `A`/`B` mark a range on the stream timeline to loop instead
+        let mut loop_mode = self.loop_mode();
+        let mut loop_points: (Option<u64>, Option<u64>) = (None, None);
+        let mut presented: u64 = 0;
+        // An error before the first frame (e.g. the input can't be opened) ends the app.
+        let mut fatal_error: Option<PlayerError> = None;
 
         event_loop.run(|event, elwt| {
             elwt.set_control_flow(ControlFlow::Poll);
@@ -58,58 +201,375 @@ impl App {
                         video_player.stop_playback();
                         elwt.exit();
                     }
+                    WindowEvent::KeyboardInput {
+                        event:
+                            KeyEvent {
+                                logical_key:
+                                    Key::Named(key @ (NamedKey::ArrowLeft | NamedKey::ArrowRight)),
+                                state: ElementState::Pressed,
+                                ..
+                            },
+                        ..
+                    } => {
+                        let target = if key == NamedKey::ArrowLeft {
+                            last_ts_us.saturating_sub(SEEK_STEP_US)
+                        } else {
+                            last_ts_us.saturating_add(SEEK_STEP_US)
+                        };
+
+                        if let Err(e) = video_player.seek(target, SeekMode::Keyframe) {
+                            eprintln!("Seek error: {e}");
+                        }
+                    }
+                    WindowEvent::KeyboardInput {
+                        event:
+                            KeyEvent {
+                                logical_key: Key::Named(NamedKey::Space),
+                                state: ElementState::Pressed,
+                                ..
+                            },
+                        ..
+                    } => {
+                        if video_player.is_paused() {
+                            video_player.resume();
+                        } else {
+                            video_player.pause();
+                        }
+                    }
+                    WindowEvent::KeyboardInput {
+                        event:
+                            KeyEvent {
+                                logical_key: Key::Character(ref c),
+                                state: ElementState::Pressed,
+                                ..
+                            },
+                        ..
+                    } if c == "." || c == "," => {
+                        let result = if c == "." {
+                            video_player.step_forward()
+                        } else {
+                            video_player.step_backward()
+                        };
+
+                        if let Err(e) = result {
+                            eprintln!("Step error: {e}");
+                        }
+                    }
+                    WindowEvent::KeyboardInput {
+                        event:
+                            KeyEvent {
+                                logical_key: Key::Character(ref c),
+                                state: ElementState::Pressed,
+                                ..
+                            },
+                        ..
+                    } if c == "[" || c == "]" => {
+                        let rate = video_player.rate();
+                        let next = if c == "[" {
+                            RATE_STEPS.iter().rev().find(|&&r| r < rate)
+                        } else {
+                            RATE_STEPS.iter().find(|&&r| r > rate)
+                        };
+
+                        if let Some(&next) = next {
+                            video_player.set_rate(next);
+                            window.set_title(&window_title(status, shown_frame.as_deref(), next));
+                        }
+                    }
+                    WindowEvent::KeyboardInput {
+                        event:
+                            KeyEvent {
+                                logical_key: Key::Character(ref c),
+                                state: ElementState::Pressed,
+                                ..
+                            },
+                        ..
+                    } if c.eq_ignore_ascii_case("t") => {
+                        let current = renderer.tone_mapping();
+                        let i = TONE_MAPPINGS.iter().position(|&t| t == current);
+                        let next = TONE_MAPPINGS[i.map_or(0, |i| (i + 1) % TONE_MAPPINGS.len())];
+                        renderer.set_tone_mapping(next);
+                    }
+                    WindowEvent::KeyboardInput {
+                        event:
+                            KeyEvent {
+                                logical_key: Key::Character(ref c),
+                                state: ElementState::Pressed,
+                                ..
+                            },
+                        ..
+                    } if c.eq_ignore_ascii_case("r") || c.eq_ignore_ascii_case("f") => {
+                        let (rotation, flip) = renderer.orientation();
+                        if c.eq_ignore_ascii_case("r") {
+                            renderer.set_orientation(rotation.then(Rotation::Cw90), flip);
+                        } else {
+                            renderer.set_orientation(rotation, !flip);
+                        }
+                    }
+                    WindowEvent::KeyboardInput {
+                        event:
+                            KeyEvent {
+                                logical_key: Key::Character(ref c),
+                                state: ElementState::Pressed,
+                                ..
+                            },
+                        ..
+                    } if c.eq_ignore_ascii_case("l") => {
+                        loop_mode = match (loop_mode, loop_points) {
+                            (LoopMode::Off, (Some(a_us), Some(b_us))) if a_us < b_us => {
+                                LoopMode::Range(a_us, b_us)
+                            }
+                            (LoopMode::Off, _) => LoopMode::Whole,
+                            _ => LoopMode::Off,
+                        };
+                        set_loop_mode(&video_player, loop_mode);
+                    }
+                    WindowEvent::KeyboardInput {
+                        event:
+                            KeyEvent {
+                                logical_key: Key::Character(ref c),
+                                state: ElementState::Pressed,
+                                ..
+                            },
+                        ..
+                    } if c.eq_ignore_ascii_case("a") || c.eq_ignore_ascii_case("b") => {
+                        let Some(frame) = shown_frame.as_ref() else {
+                            return;
+                        };
+                        if c.eq_ignore_ascii_case("a") {
+                            loop_points.0 = Some(frame.stream_ts_us);
+                        } else {
+                            loop_points.1 = Some(frame.stream_ts_us);
+                        }
+
+                        // Both points set → loop between them right away
+                        if let (Some(a_us), Some(b_us)) = loop_points {
+                            if a_us < b_us {
+                                loop_mode = LoopMode::Range(a_us, b_us);
+                                set_loop_mode(&video_player, loop_mode);
+                            }
+                        }
+                    }
+                    WindowEvent::KeyboardInput {
+                        event:
+                            KeyEvent {
+                                logical_key: Key::Character(ref c),
+                                state: ElementState::Pressed,
+                                ..
+                            },
+                        ..
+                    } if c.eq_ignore_ascii_case("s") => {
+                        if let Some(frame) = shown_frame.clone() {
+                            let screen = match renderer.capture() {
+                                Ok(image) => Some(image),
+                                Err(e) => {
+                                    eprintln!("Capture error: {e}");
+                                    None
+                                }
+                            };
+                            let name = format!("{snapshot_name}_{}", frame.stream_ts_us);
+                            // Encoding PNGs takes a while → keep it off the event loop
+                            thread::spawn(move || save_snapshot(&name, &frame, screen));
+                        }
+                    }
                     WindowEvent::RedrawRequested => {
-                        if let Some(frame) = video_player.get_latest_frame() {
-                            if frame.ts_us != last_ts_us {
-                                // First real frame → set video size + resize window to match
-                                if first_frame_size.is_none() {
-                                    first_frame_size = Some((frame.width, frame.height));
-                                    renderer.set_video_size(frame.width, frame.height);
-
-                                    let logical =
-                                        LogicalSize::new(frame.width as f64, frame.height as f64);
+                        let mut title_changed = false;
+                        loop {
+                            let event = match events.try_recv() {
+                                Ok(event) => event,
+                                Err(TryRecvError::Lagged(_)) => continue,
+                                Err(_) => break,
+                            };
+
+                            status = match event {
+                                PlayerEvent::Opened(_)
+                                | PlayerEvent::Seeked
+                                | PlayerEvent::FormatChanged { .. } => continue,
+                                PlayerEvent::Playing => None,
+                                PlayerEvent::Paused => Some("paused"),
+                                PlayerEvent::Buffering => Some("buffering"),
+                                // `Playing` follows once frames arrive again
+                                PlayerEvent::Reconnected => continue,
+                                PlayerEvent::Reconnecting { .. } => Some("reconnecting"),
+                                PlayerEvent::EndOfStream => Some("ended"),
+                                PlayerEvent::Error(e) if video_size.is_none() => {
+                                    fatal_error = Some(e);
+                                    elwt.exit();
+                                    return;
+                                }
+                                PlayerEvent::Error(e) => {
+                                    eprintln!("Playback error: {e}");
+                                    Some("error")
+                                }
+                            };
+                            title_changed = true;
+                        }
+
+                        // Present once the shared playback clock reaches the frame's timestamp;
+                        // without pacing every queued frame in turn
+                        let frame = match args.no_pacing {
+                            true => video_player.next_queued_frame(),
+                            false => video_player.next_due_frame(),
+                        };
+                        if let Some(frame) = frame {
+                            // New size (first frame or a mid-stream switch) → new aspect ratio;
+                            // only the first one resizes the window to match
+                            let (width, height) = frame.display_size();
+                            if video_size != Some((width, height)) {
+                                if video_size.is_none()
+                                    && args.window_size.is_none()
+                                    && !args.fullscreen
+                                {
+                                    let logical = LogicalSize::new(width as f64, height as f64);
                                     let _ = window.request_inner_size(logical);
                                 }
+                                video_size = Some((width, height));
+                                renderer.set_video_size(width, height);
+                            }
 
-                                renderer.set_frame_data(frame.width, frame.height, &frame.data);
+                            renderer.set_frame(&frame);
+                            last_ts_us = frame.ts_us;
+                            shown_frame = Some(frame);
 
-                                last_ts_us = frame.ts_us;
-                                if base_ts_us.is_none() {
-                                    base_ts_us = Some(frame.ts_us);
-                                    wall_start = Some(Instant::now());
-                                }
+                            // Frame-by-frame inspection → keep the PTS on screen
+                            title_changed |= status.is_some();
+
+                            presented += 1;
+                            if args.frames.is_some_and(|frames| presented >= frames) {
+                                video_player.stop_playback();
+                                elwt.exit();
                             }
                         }
 
-                        if let Err(e) = renderer.render() {
-                            eprint!("Render error: {e}");
+                        if title_changed {
+                            let title =
+                                window_title(status, shown_frame.as_deref(), video_player.rate());
+                            window.set_title(&title);
                         }
 
-                        //window.request_redraw();
-                        if let (Some(bts), Some(ws)) = (base_ts_us, wall_start) {
-                            let target = ws + Duration::from_micros(last_ts_us.saturating_sub(bts));
-                            let now = Instant::now();
-                            if target > now {
-                                elwt.set_control_flow(ControlFlow::WaitUntil(target));
-                            } else {
-                                // Behind → redraw ASAP to catch up
-                                elwt.set_control_flow(ControlFlow::Poll);
-                                window.request_redraw();
-                            }
-                        } else {
-                            // No baseline yet → keep polling
-                            elwt.set_control_flow(ControlFlow::Poll);
-                            window.request_redraw();
+                        if let Err(e) = renderer.render() {
+                            eprint!("Render error: {e}");
                         }
                     }
                     _ => {}
                 },
-                Event::AboutToWait => {
+                Event::NewEvents(StartCause::ResumeTimeReached { .. }) => {
                     window.request_redraw();
                 }
+                Event::AboutToWait => {
+                    if clock.is_paused() {
+                        elwt.set_control_flow(ControlFlow::WaitUntil(
+                            Instant::now() + PAUSED_REDRAW_INTERVAL,
+                        ));
+                    } else {
+                        window.request_redraw();
+                    }
+                }
                 _ => {}
             }
         })?;
-        Ok(())
+
+        print_stats(&video_player);
+        match fatal_error {
+            Some(e) => Err(e.into()),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Sleep until the playback clock reaches `ts_us`.
+async fn wait_until_due(clock: &PlaybackClock, ts_us: u64) {
+    loop {
+        let now_us = clock.now_us();
+        if ts_us <= now_us {
+            return;
+        }
+        let wait = Duration::from_micros(((ts_us - now_us) as f64 / clock.rate()) as u64);
+        tokio::time::sleep(wait.min(MAX_DUE_WAIT)).await;
+    }
+}
+
+/// Frame counters, printed on exit.
+fn print_stats(video_player: &VideoPlayer) {
+    let stats = video_player.stats();
+    println!(
+        "Presented {} of {} frames ({} dropped late, {} overwritten); {} of {} frame buffers reused",
+        stats.presented,
+        stats.pushed,
+        stats.dropped_late,
+        stats.dropped_overwritten,
+        stats.pool_hits,
+        stats.pool_hits + stats.pool_misses
+    );
+}
+
+/// Apply `mode` and report it on stdout.
+fn set_loop_mode(video_player: &VideoPlayer, mode: LoopMode) {
+    match video_player.set_loop_mode(mode) {
+        Ok(()) => match mode {
+            LoopMode::Off => println!("Loop off"),
+            LoopMode::Whole => println!("Looping"),
+            LoopMode::Range(a_us, b_us) => {
+                println!("Looping {} - {}", timestamp(a_us), timestamp(b_us))
+            }
+        },
+        Err(e) => eprintln!("Loop error: {e}"),
+    }
+}
+
+/// Window title with the playback state, e.g. `WGPU Video Player [paused 0:01:23.456] (2x)`.
+fn window_title(status: Option<&str>, frame: Option<&Frame>, rate: f64) -> String {
+    let mut title = WINDOW_TITLE.to_string();
+
+    if let Some(status) = status {
+        let ts_us = frame.map_or(0, |frame| frame.stream_ts_us);
+        title += &format!(" [{status} {}]", timestamp(ts_us));
+    }
+
+    if rate != 1.0 {
+        title += &format!(" ({rate}x)");
+    }
+
+    title
+}
+
+/// `h:mm:ss.fff`
+fn timestamp(ts_us: u64) -> String {
+    let ms = ts_us / 1_000;
+    format!(
+        "{}:{:02}:{:02}.{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1_000 % 60,
+        ms % 1_000
+    )
+}
+
+/// Prefix for snapshot files: the input's file stem, e.g. `x36xhzz` for
+/// `https://test-streams.mux.dev/x36xhzz/x36xhzz.m3u8`.
+fn snapshot_basename(input: &str) -> String {
+    let path = input.split(['?', '#']).next().unwrap_or(input);
+    Path::new(path)
+        .file_stem()
+        .map_or("snapshot".to_string(), |stem| {
+            stem.to_string_lossy().into_owned()
+        })
+}
+
+/// Write `<name>.png` with the decoded frame at its native resolution and
+/// `<name>_screen.png` with the window contents, letterbox included.
+fn save_snapshot(name: &str, frame: &Frame, screen: Option<CapturedImage>) {
+    let path = format!("{name}.png");
+    match VideoPlayer::save_frame(frame, Path::new(&path)) {
+        Ok(()) => println!("Saved {path}"),
+        Err(e) => eprintln!("{e}"),
+    }
+
+    if let Some(image) = screen {
+        let path = format!("{name}_screen.png");
+        match write_png(Path::new(&path), image.width, image.height, &image.rgba) {
+            Ok(()) => println!("Saved {path}"),
+            Err(e) => eprintln!("Failed to save {path}: {e}"),
+        }
     }
 }
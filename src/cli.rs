@@ -0,0 +1,218 @@
+use clap::{ArgGroup, Parser};
+use rs_wgpu_video_player::video_player::{
+    OutputSize, PixelFormat, ScaleFilter, MAX_RATE, MIN_RATE,
+};
+use std::{num::NonZeroUsize, time::Duration};
+
+/// Play a video file or stream, decoded with FFmpeg and rendered with WGPU.
+#[derive(Parser)]
+#[command(version, about)]
+#[command(group(ArgGroup::new("info").args(["probe", "keyframes"])))]
+pub struct Args {
+    /// File path or URL (HLS playlists, RTMP, ...)
+    pub input: String,
+
+    /// Start position, in seconds or as [hh:]mm:ss[.fff]
+    #[arg(long, value_name = "TIME", value_parser = parse_time)]
+    pub start: Option<u64>,
+
+    /// Start over at the end of the input
+    #[arg(long = "loop")]
+    pub looping: bool,
+
+    /// Playback speed, from 0.25 to 4
+    #[arg(long, default_value_t = 1.0, value_parser = parse_speed)]
+    pub speed: f64,
+
+    /// Frame layout handed to the renderer: rgba, rgb24, bgra, gray8, yuv420p, nv12 or p010
+    #[arg(long, value_name = "FORMAT", default_value = "yuv420p", value_parser = parse_pixel_format)]
+    pub pixel_format: PixelFormat,
+
+    /// Decoded frame size: native, WxH (exact), fit:WxH (shrink to fit) or
+    /// e.g. 720p (shrink to at most 720 rows)
+    #[arg(long, value_name = "SIZE", default_value = "native", value_parser = parse_output_size)]
+    pub output_size: OutputSize,
+
+    /// Filter for --output-size: bilinear, bicubic, lanczos, area or point
+    #[arg(long, value_name = "FILTER", default_value = "bilinear", value_parser = parse_scale_filter)]
+    pub scale_filter: ScaleFilter,
+
+    /// Show frames as they arrive, for live streams
+    #[arg(long)]
+    pub live: bool,
+
+    /// Give up on a read or open of the input that blocks longer than this
+    /// many seconds; 0 waits indefinitely
+    #[arg(long, value_name = "SECONDS", default_value = "10", value_parser = parse_timeout)]
+    pub read_timeout: Duration,
+
+    /// With --live: reopen a lost input up to N times in a row, backing off
+    /// exponentially; 0 stops at the first loss
+    #[arg(long, value_name = "N", default_value_t = 10)]
+    pub reconnect_attempts: u32,
+
+    /// Open a borderless fullscreen window
+    #[arg(long, conflicts_with = "headless")]
+    pub fullscreen: bool,
+
+    /// Window size as WIDTHxHEIGHT instead of the video size
+    #[arg(long, value_name = "WxH", value_parser = parse_size, conflicts_with = "headless")]
+    pub window_size: Option<(u32, u32)>,
+
+    /// Decode and present as fast as possible, without audio
+    #[arg(long)]
+    pub no_pacing: bool,
+
+    /// Queue up to N decoded frames instead of only the latest one; the
+    /// decoder waits while the queue is full
+    #[arg(long, value_name = "N")]
+    pub queue_depth: Option<NonZeroUsize>,
+
+    /// Print information about the input and exit
+    #[arg(long)]
+    pub probe: bool,
+
+    /// Print the keyframes of the video stream and exit. The full packet index
+    /// of a local file is cached next to it in `<input>.keyframes`.
+    #[arg(long)]
+    pub keyframes: bool,
+
+    /// With --probe or --keyframes: print JSON
+    #[arg(long, requires = "info")]
+    pub json: bool,
+
+    /// Exit after presenting N frames
+    #[arg(long, value_name = "N")]
+    pub frames: Option<u64>,
+
+    /// Render offscreen without opening a window
+    #[arg(long)]
+    pub headless: bool,
+}
+
+/// Seconds (`90.5`) or colon-separated minutes and hours (`1:30.5`, `0:01:30.5`) → µs.
+fn parse_time(s: &str) -> Result<u64, String> {
+    let mut seconds = 0.0;
+    for part in s.split(':') {
+        let value = part
+            .parse::<f64>()
+            .ok()
+            .filter(|v| v.is_finite() && *v >= 0.0)
+            .ok_or_else(|| format!("invalid time `{s}`"))?;
+        seconds = seconds * 60.0 + value;
+    }
+    Ok((seconds * 1_000_000.0).round() as u64)
+}
+
+/// Seconds → `Duration`; 0 (no timeout) is `Duration::ZERO`.
+fn parse_timeout(s: &str) -> Result<Duration, String> {
+    match s.parse::<f64>() {
+        Ok(seconds) if seconds.is_finite() && seconds >= 0.0 => {
+            Ok(Duration::from_secs_f64(seconds))
+        }
+        _ => Err(format!("expected a number of seconds, not `{s}`")),
+    }
+}
+
+fn parse_speed(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(speed) if (MIN_RATE..=MAX_RATE).contains(&speed) => Ok(speed),
+        _ => Err(format!("must be a number from {MIN_RATE} to {MAX_RATE}")),
+    }
+}
+
+fn parse_pixel_format(s: &str) -> Result<PixelFormat, String> {
+    match s.to_ascii_lowercase().as_str() {
+        "rgba" => Ok(PixelFormat::RGBA),
+        "rgb24" => Ok(PixelFormat::RGB24),
+        "bgra" => Ok(PixelFormat::BGRA),
+        "gray8" => Ok(PixelFormat::GRAY8),
+        "yuv420p" => Ok(PixelFormat::Yuv420p),
+        "nv12" => Ok(PixelFormat::Nv12),
+        "p010" => Ok(PixelFormat::P010),
+        _ => Err("expected rgba, rgb24, bgra, gray8, yuv420p, nv12 or p010".to_string()),
+    }
+}
+
+fn parse_output_size(s: &str) -> Result<OutputSize, String> {
+    let s = s.to_ascii_lowercase();
+    if s == "native" {
+        return Ok(OutputSize::Native);
+    }
+    if let Some(rows) = s.strip_suffix('p') {
+        return rows
+            .parse()
+            .ok()
+            .filter(|&rows| rows > 0)
+            .map(OutputSize::MaxHeight)
+            .ok_or_else(|| format!("expected e.g. 720p, not `{s}`"));
+    }
+    match s.strip_prefix("fit:") {
+        Some(size) => parse_size(size).map(|(w, h)| OutputSize::FitWithin(w, h)),
+        None => parse_size(&s).map(|(w, h)| OutputSize::Fixed(w, h)),
+    }
+}
+
+fn parse_scale_filter(s: &str) -> Result<ScaleFilter, String> {
+    match s.to_ascii_lowercase().as_str() {
+        "bilinear" => Ok(ScaleFilter::Bilinear),
+        "bicubic" => Ok(ScaleFilter::Bicubic),
+        "lanczos" => Ok(ScaleFilter::Lanczos),
+        "area" => Ok(ScaleFilter::Area),
+        "point" => Ok(ScaleFilter::Point),
+        _ => Err("expected bilinear, bicubic, lanczos, area or point".to_string()),
+    }
+}
+
+fn parse_size(s: &str) -> Result<(u32, u32), String> {
+    s.split_once(['x', 'X'])
+        .and_then(|(w, h)| Some((w.parse().ok()?, h.parse().ok()?)))
+        .filter(|&(w, h)| w > 0 && h > 0)
+        .ok_or_else(|| format!("expected WIDTHxHEIGHT, e.g. 1280x720, not `{s}`"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn definition_is_consistent() {
+        Args::command().debug_assert();
+    }
+
+    #[test]
+    fn output_size() {
+        assert_eq!(parse_output_size("native"), Ok(OutputSize::Native));
+        assert_eq!(
+            parse_output_size("1280x720"),
+            Ok(OutputSize::Fixed(1280, 720))
+        );
+        assert_eq!(
+            parse_output_size("640X360"),
+            Ok(OutputSize::Fixed(640, 360))
+        );
+        assert_eq!(
+            parse_output_size("fit:1920x1080"),
+            Ok(OutputSize::FitWithin(1920, 1080))
+        );
+        assert_eq!(parse_output_size("720p"), Ok(OutputSize::MaxHeight(720)));
+        assert_eq!(parse_output_size("720P"), Ok(OutputSize::MaxHeight(720)));
+        for bad in [
+            "", "0p", "p", "1280", "1280x", "0x720", "fit:720p", "-1x720",
+        ] {
+            assert!(parse_output_size(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn read_timeout() {
+        let args = Args::try_parse_from(["player", "in.mp4"]).unwrap();
+        assert_eq!(args.read_timeout, Duration::from_secs(10));
+        let args = Args::try_parse_from(["player", "in.mp4", "--read-timeout", "0"]).unwrap();
+        assert_eq!(args.read_timeout, Duration::ZERO);
+        let args = Args::try_parse_from(["player", "in.mp4", "--read-timeout", "2.5"]).unwrap();
+        assert_eq!(args.read_timeout, Duration::from_millis(2500));
+        assert!(Args::try_parse_from(["player", "in.mp4", "--read-timeout", "-1"]).is_err());
+    }
+}
@@ -0,0 +1,18 @@
+//! FFmpeg video decoding with A/V-synced playback, plus a WGPU renderer for
+//! presenting the decoded frames.
+//!
+//! The renderer needs the `render` feature and sound output the `audio-device`
+//! feature (both on by default). Without them the crate only depends on FFmpeg
+//! and Tokio, so `VideoPlayer` can run on headless machines. The `serde`
+//! feature makes `MediaInfo` serializable.
+
+pub mod video_player;
+pub use video_player::{
+    get_media_info, get_video_info, Frame, FrameBuffer, MediaInfo, PlaybackParams, PlayerError,
+    VideoInfo, VideoPlayer,
+};
+
+#[cfg(feature = "render")]
+pub mod renderer;
+#[cfg(feature = "render")]
+pub use renderer::Renderer;
@@ -1,17 +1,176 @@
-use std::{env, error::Error};
+use clap::Parser;
+use rs_wgpu_video_player::{
+    get_media_info,
+    video_player::{load_keyframe_index, KeyframeIndex},
+    MediaInfo, PlayerError,
+};
+use std::{error::Error, process::ExitCode};
 
 mod app;
 use app::App;
 
-mod renderer;
-mod video_player;
+mod cli;
+use cli::Args;
+
+// Exit codes besides success; clap exits with 2 on invalid arguments.
+const EXIT_FAILURE: u8 = 1;
+/// The input could not be opened or read.
+const EXIT_OPEN_FAILED: u8 = 3;
+/// The input has no video stream FFmpeg can decode.
+const EXIT_UNSUPPORTED: u8 = 4;
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn Error>> {
-    let url = env::args()
-        .nth(1)
-        .unwrap_or_else(|| "https://test-streams.mux.dev/x36xhzz/x36xhzz.m3u8".to_string());
+async fn main() -> ExitCode {
+    let args = Args::parse();
+
+    let result = if args.probe {
+        probe(&args.input, args.json)
+    } else if args.keyframes {
+        keyframes(&args.input, args.json)
+    } else {
+        App::new(args).run().await
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            ExitCode::from(exit_code(e.as_ref()))
+        }
+    }
+}
+
+fn probe(input: &str, json: bool) -> Result<(), Box<dyn Error>> {
+    let info = get_media_info(input)?;
+    match json {
+        true => println!("{}", serde_json::to_string_pretty(&info)?),
+        false => print_media_info(&info),
+    }
+    Ok(())
+}
+
+fn keyframes(input: &str, json: bool) -> Result<(), Box<dyn Error>> {
+    let index = load_keyframe_index(input)?;
+    match json {
+        true => println!("{}", serde_json::to_string_pretty(&index)?),
+        false => print_keyframes(&index),
+    }
+    Ok(())
+}
+
+/// One line per keyframe, then a summary of the whole stream.
+fn print_keyframes(index: &KeyframeIndex) {
+    println!("{:>14}  {:>12}  {:>9}", "pts", "byte_pos", "size");
+    for keyframe in index.keyframes() {
+        let byte_pos = keyframe
+            .byte_pos
+            .map_or("-".to_string(), |pos| pos.to_string());
+        println!(
+            "{:>14}  {byte_pos:>12}  {:>9}",
+            seconds(keyframe.pts_us),
+            keyframe.size
+        );
+    }
+
+    let pts: Vec<i64> = index.keyframes().map(|keyframe| keyframe.pts_us).collect();
+    let longest_gop_us = pts.windows(2).map(|w| w[1] - w[0]).max();
+    print!("{} packets, {} keyframes", index.entries.len(), pts.len());
+    match longest_gop_us {
+        Some(gop_us) => println!(", longest GOP {}", seconds(gop_us)),
+        None => println!(),
+    }
+}
+
+/// One line per stream and chapter, roughly like `ffprobe`.
+fn print_media_info(info: &MediaInfo) {
+    println!("Format: {} ({})", info.format_name, info.format_long_name);
+    if let Some(duration_us) = info.duration_us {
+        println!("Duration: {}", seconds(duration_us));
+    }
+    if let Some(bit_rate) = info.bit_rate {
+        println!("Bitrate: {} kb/s", bit_rate / 1000);
+    }
+    for (key, value) in &info.metadata {
+        println!("  {key}: {value}");
+    }
+
+    for chapter in &info.chapters {
+        let title = chapter.title.as_deref().unwrap_or("");
+        let (start, end) = (seconds(chapter.start_us), seconds(chapter.end_us));
+        println!("Chapter #{}: {start} - {end} {title}", chapter.id);
+    }
+
+    for stream in &info.streams {
+        let mut details = vec![match &stream.profile {
+            Some(profile) => format!("{} ({profile})", stream.codec),
+            None => stream.codec.clone(),
+        }];
+        if let Some(level) = stream.level {
+            details.push(format!("level {level}"));
+        }
+
+        if let Some(video) = &stream.video {
+            details.extend(video.pixel_format.clone());
+            details.push(format!("{}x{}", video.width, video.height));
+            if let Some((num, den)) = video.sample_aspect_ratio {
+                details.push(format!("SAR {num}:{den}"));
+            }
+            if let Some(fps) = video.avg_fps {
+                details.push(format!("{fps:.3} fps"));
+            }
+            if let Some(rotation) = video.rotation.filter(|&r| r != 0.0) {
+                details.push(format!("rotated {rotation}°"));
+            }
+            let color = [
+                &video.color_space,
+                &video.color_range,
+                &video.color_primaries,
+                &video.color_transfer,
+            ];
+            if color.iter().any(|tag| tag.is_some()) {
+                let tags = color.map(|tag| tag.as_deref().unwrap_or("unknown"));
+                details.push(tags.join("/"));
+            }
+        }
+
+        if let Some(audio) = &stream.audio {
+            details.push(format!("{} Hz", audio.sample_rate));
+            details.push(match &audio.channel_layout {
+                Some(layout) => layout.clone(),
+                None => format!("{} channels", audio.channels),
+            });
+            details.extend(audio.sample_format.clone());
+        }
+
+        if let Some(bit_rate) = stream.bit_rate {
+            details.push(format!("{} kb/s", bit_rate / 1000));
+        }
+
+        let mut labels = vec![format!("{:?}", stream.kind).to_lowercase()];
+        labels.extend(stream.language.clone());
+        if stream.is_default {
+            labels.push("default".to_string());
+        }
+
+        println!(
+            "Stream #{} ({}): {}",
+            stream.index,
+            labels.join(", "),
+            details.join(", ")
+        );
+    }
+}
+
+fn seconds(us: i64) -> String {
+    format!("{:.3} s", us as f64 / 1_000_000.0)
+}
 
-    let app = App {};
-    app.run(url).await
+fn exit_code(e: &(dyn Error + 'static)) -> u8 {
+    match e.downcast_ref::<PlayerError>() {
+        Some(PlayerError::Open { .. } | PlayerError::Timeout | PlayerError::InputLost(_)) => {
+            EXIT_OPEN_FAILED
+        }
+        Some(PlayerError::NoVideoStream | PlayerError::UnsupportedCodec(_)) => EXIT_UNSUPPORTED,
+        _ => EXIT_FAILURE,
+    }
 }
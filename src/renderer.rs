@@ -1,33 +1,265 @@
-use std::error::Error;
+use crate::video_player::{
+    ColorInfo, ColorPrimaries, ColorRange, Frame, PixelFormat, Rotation, TransferFunction,
+};
+use std::{error::Error, num::NonZeroU64, sync::mpsc};
 use wgpu::{
-    AddressMode, BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout,
-    BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingResource, BindingType, Color,
-    CommandEncoderDescriptor, Device, DeviceDescriptor, Extent3d, FilterMode, FragmentState,
-    Instance, LoadOp, MultisampleState, Operations, Origin3d, PipelineCompilationOptions,
-    PipelineLayoutDescriptor, PresentMode, PrimitiveState, Queue, RenderPassColorAttachment,
-    RenderPassDescriptor, RenderPipeline, RenderPipelineDescriptor, RequestAdapterOptions, Sampler,
-    SamplerBindingType, SamplerDescriptor, ShaderModuleDescriptor, ShaderStages, StoreOp, Surface,
-    SurfaceConfiguration, SurfaceError, TexelCopyBufferLayout, TexelCopyTextureInfo, Texture,
-    TextureAspect, TextureDescriptor, TextureDimension, TextureFormat, TextureSampleType,
-    TextureUsages, TextureViewDescriptor, TextureViewDimension, VertexState,
+    Adapter, AddressMode, BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout,
+    BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingResource, BindingType, Buffer,
+    BufferBindingType, BufferDescriptor, BufferUsages, Color, CommandEncoderDescriptor, Device,
+    DeviceDescriptor, Extent3d, FilterMode, FragmentState, Instance, LoadOp, MapMode,
+    MultisampleState, Operations, Origin3d, PipelineCompilationOptions, PipelineLayoutDescriptor,
+    PollType, PresentMode, PrimitiveState, Queue, RenderPassColorAttachment, RenderPassDescriptor,
+    RenderPipeline, RenderPipelineDescriptor, RequestAdapterOptions, Sampler, SamplerBindingType,
+    SamplerDescriptor, ShaderModuleDescriptor, ShaderStages, StoreOp, Surface,
+    SurfaceConfiguration, SurfaceError, TexelCopyBufferInfo, TexelCopyBufferLayout,
+    TexelCopyTextureInfo, Texture, TextureAspect, TextureDescriptor, TextureDimension,
+    TextureFormat, TextureSampleType, TextureUsages, TextureView, TextureViewDescriptor,
+    TextureViewDimension, VertexState, COPY_BYTES_PER_ROW_ALIGNMENT,
 };
 use winit::{dpi::PhysicalSize, window::Window};
 
 struct FrameTexture {
-    texture: Texture,
+    // One texture per plane of `format`
+    planes: Vec<Texture>,
+    // Frame rows repacked to `COPY_BYTES_PER_ROW_ALIGNMENT`, copied into `planes` on the GPU
+    staging: Buffer,
     bind: BindGroup,
     width: u32,
     height: u32,
+    format: PixelFormat,
+    color: ColorInfo,
+    rotation: Rotation,
+}
+
+/// Operator that squeezes HDR (PQ or HLG) content into SDR range.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ToneMapping {
+    /// ITU-R BT.2390 EETF: keeps midtones and rolls off the highlights.
+    #[default]
+    Bt2390,
+    /// Filmic curve from Uncharted 2.
+    Hable,
+    /// Extended Reinhard with the white point at the content peak.
+    Reinhard,
+}
+
+/// Assumed content peak in cd/m² when the stream carries no HDR metadata.
+const DEFAULT_HDR_PEAK: f32 = 1000.0;
+
+/// Mirrors `Params` in shader.wgsl.
+struct ShaderParams {
+    format: u32,
+    transfer: u32,
+    primaries: u32,
+    tone_mapping: u32,
+    // Column-major affine YUV → RGB transform, range expansion included
+    yuv_to_rgb: [[f32; 4]; 4],
+    peak_luminance: f32,
+    // Clockwise quarter turns, then a horizontal mirror if non-zero
+    rotation: u32,
+    flip: u32,
+}
+
+impl ShaderParams {
+    const SIZE: u64 = 96;
+
+    fn new(
+        format: PixelFormat,
+        color: ColorInfo,
+        tone_mapping: ToneMapping,
+        rotation: Rotation,
+        flip: bool,
+    ) -> Self {
+        let (format, bits) = match format {
+            PixelFormat::RGBA | PixelFormat::RGB24 | PixelFormat::BGRA => (0, 8),
+            PixelFormat::GRAY8 => (4, 8),
+            PixelFormat::Yuv420p => (1, 8),
+            PixelFormat::Nv12 => (2, 8),
+            PixelFormat::P010 => (3, 10),
+        };
+        let transfer = match color.transfer {
+            TransferFunction::Sdr => 0,
+            TransferFunction::Linear => 1,
+            TransferFunction::Pq => 2,
+            TransferFunction::Hlg => 3,
+        };
+        let primaries = match color.primaries {
+            ColorPrimaries::Bt709 | ColorPrimaries::Bt601 => 0,
+            ColorPrimaries::Bt2020 => 1,
+        };
+        let tone_mapping = match tone_mapping {
+            ToneMapping::Bt2390 => 0,
+            ToneMapping::Hable => 1,
+            ToneMapping::Reinhard => 2,
+        };
+        let peak_luminance = match color.transfer {
+            // HLG is display-referred to a nominal 1000 cd/m² peak
+            TransferFunction::Hlg => DEFAULT_HDR_PEAK,
+            _ => color
+                .hdr
+                .and_then(|hdr| hdr.peak_luminance())
+                .unwrap_or(DEFAULT_HDR_PEAK),
+        };
+
+        Self {
+            format,
+            transfer,
+            primaries,
+            tone_mapping,
+            yuv_to_rgb: yuv_to_rgb(color, bits),
+            peak_luminance,
+            rotation: rotation.quarter_turns(),
+            flip: flip as u32,
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(Self::SIZE as usize);
+        for v in [
+            self.format,
+            self.transfer,
+            self.primaries,
+            self.tone_mapping,
+        ] {
+            bytes.extend(v.to_le_bytes());
+        }
+        for v in self.yuv_to_rgb.iter().flatten() {
+            bytes.extend(v.to_le_bytes());
+        }
+        bytes.extend(self.peak_luminance.to_le_bytes());
+        bytes.extend(self.rotation.to_le_bytes());
+        bytes.extend(self.flip.to_le_bytes());
+        // Struct size rounds up to its 16-byte alignment
+        bytes.resize(Self::SIZE as usize, 0);
+        bytes
+    }
+}
+
+/// YUV → RGB for the frame's matrix, range and bit depth, as a column-major
+/// 4x4 so the offsets fold into the last column.
+fn yuv_to_rgb(color: ColorInfo, bits: u32) -> [[f32; 4]; 4] {
+    let (kr, kb) = color.matrix.luma_weights();
+    let kg = 1.0 - kr - kb;
+
+    // Code values are normalized to 0..1 by the texture; map them to Y in
+    // 0..1 and U/V in -0.5..0.5. Limited range levels scale with bit depth.
+    let max = ((1 << bits) - 1) as f32;
+    let step = (1 << (bits - 8)) as f32;
+    let (y_scale, c_scale, y_offset) = match color.range {
+        ColorRange::Limited => (
+            max / (219.0 * step),
+            max / (224.0 * step),
+            16.0 * step / max,
+        ),
+        ColorRange::Full => (1.0, 1.0, 0.0),
+    };
+    let c_offset = 128.0 * step / max;
+    let offset = [y_offset, c_offset, c_offset];
+
+    // Rows R, G, B over columns Y, U, V
+    let m = [
+        [y_scale, 0.0, c_scale * 2.0 * (1.0 - kr)],
+        [
+            y_scale,
+            -c_scale * 2.0 * kb * (1.0 - kb) / kg,
+            -c_scale * 2.0 * kr * (1.0 - kr) / kg,
+        ],
+        [y_scale, c_scale * 2.0 * (1.0 - kb), 0.0],
+    ];
+
+    let mut columns = [[0.0; 4]; 4];
+    for (row, coefficients) in m.iter().enumerate() {
+        for (col, c) in coefficients.iter().enumerate() {
+            columns[col][row] = *c;
+        }
+        columns[3][row] = -coefficients
+            .iter()
+            .zip(offset)
+            .map(|(c, o)| c * o)
+            .sum::<f32>();
+    }
+    columns[3][3] = 1.0;
+    columns
+}
+
+/// Texture format and chroma subsampling shift of each plane, in `Frame::data` order.
+fn plane_layout(format: PixelFormat) -> &'static [(TextureFormat, u32)] {
+    match format {
+        // RGB24 has no matching texture format; expanded to RGBA on upload
+        PixelFormat::RGBA | PixelFormat::RGB24 => &[(TextureFormat::Rgba8UnormSrgb, 0)],
+        PixelFormat::BGRA => &[(TextureFormat::Bgra8UnormSrgb, 0)],
+        PixelFormat::GRAY8 => &[(TextureFormat::R8Unorm, 0)],
+        PixelFormat::Yuv420p => &[
+            (TextureFormat::R8Unorm, 0),
+            (TextureFormat::R8Unorm, 1),
+            (TextureFormat::R8Unorm, 1),
+        ],
+        PixelFormat::Nv12 => &[(TextureFormat::R8Unorm, 0), (TextureFormat::Rg8Unorm, 1)],
+        // 16-bit samples split into low/high bytes; the shader recombines them.
+        // Filtering stays exact because the recombination is linear.
+        PixelFormat::P010 => &[(TextureFormat::Rg8Unorm, 0), (TextureFormat::Rgba8Unorm, 1)],
+    }
+}
+
+/// Copy rows of `row` bytes, `stride` apart in `src`, to `pitch` apart in `dst`:
+/// from the decoder's stride to wgpu's alignment. RGB24 is expanded to RGBA
+/// on the way, with opaque alpha.
+fn repack_rows(
+    format: PixelFormat,
+    src: &[u8],
+    stride: usize,
+    row: usize,
+    dst: &mut [u8],
+    pitch: usize,
+) {
+    for (dst, src) in dst.chunks_mut(pitch).zip(src.chunks(stride)) {
+        let src = &src[..row];
+        match format {
+            PixelFormat::RGB24 => {
+                for (rgba, rgb) in dst.chunks_exact_mut(4).zip(src.chunks_exact(3)) {
+                    rgba[..3].copy_from_slice(rgb);
+                    rgba[3] = u8::MAX;
+                }
+            }
+            _ => dst[..row].copy_from_slice(src),
+        }
+    }
+}
+
+/// Where frames are drawn.
+enum Target<'r> {
+    Window {
+        surface: Surface<'r>,
+        config: SurfaceConfiguration,
+    },
+    /// Owned color texture, read back through `readback` on request.
+    Offscreen { texture: Texture, readback: Buffer },
+}
+
+/// Format of the offscreen target and of frames read back from it.
+const OFFSCREEN_FORMAT: TextureFormat = TextureFormat::Rgba8UnormSrgb;
+
+/// Image read back from the renderer, as tightly packed sRGB RGBA rows.
+pub struct CapturedImage {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
 }
 
 pub struct Renderer<'r> {
-    surface: Surface<'r>,
+    target: Target<'r>,
+    // Color format of the target and the pipeline
+    format: TextureFormat,
     device: Device,
     queue: Queue,
-    config: SurfaceConfiguration,
     sampler: Sampler,
     pipeline: RenderPipeline,
     bind_layout: BindGroupLayout,
+    params: Buffer,
+    tone_mapping: ToneMapping,
+    // Manual orientation on top of the frame's own rotation
+    rotation: Rotation,
+    flip: bool,
     frame_tex: Option<FrameTexture>,
     surf_w: u32,
     surf_h: u32,
@@ -52,15 +284,7 @@ impl<'r> Renderer<'r> {
             .await
             .map_err(|e| format!("Failed to find an appropriate adapter: {}", e))?;
 
-        let device_desc = DeviceDescriptor {
-            label: Some("GPU Device"),
-            ..Default::default()
-        };
-
-        let (device, queue) = adapter
-            .request_device(&device_desc)
-            .await
-            .map_err(|e| format!("Failed to create device: {}", e))?;
+        let (device, queue) = request_device(&adapter).await?;
 
         let surface_caps = surface.get_capabilities(&adapter);
         let surface_format = surface_caps
@@ -84,6 +308,62 @@ impl<'r> Renderer<'r> {
 
         surface.configure(&device, &config);
 
+        Ok(Self::with_target(
+            device,
+            queue,
+            Target::Window { surface, config },
+            surface_format,
+            size,
+        ))
+    }
+
+    /// Render without a window into an owned `width`x`height` texture, e.g. on
+    /// CI or a server. Falls back to a software adapter when there is no GPU.
+    /// Read each frame back with `render_to_rgba`.
+    pub async fn new_headless(
+        width: u32,
+        height: u32,
+    ) -> Result<Renderer<'static>, Box<dyn Error>> {
+        let instance = Instance::default();
+
+        let mut adapter_options = RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            compatible_surface: None,
+            force_fallback_adapter: false,
+        };
+
+        let adapter = match instance.request_adapter(&adapter_options).await {
+            Ok(adapter) => adapter,
+            Err(_) => {
+                adapter_options.force_fallback_adapter = true;
+                instance
+                    .request_adapter(&adapter_options)
+                    .await
+                    .map_err(|e| format!("Failed to find an appropriate adapter: {}", e))?
+            }
+        };
+
+        let (device, queue) = request_device(&adapter).await?;
+
+        let size = PhysicalSize::new(width.max(1), height.max(1));
+        let (texture, readback) = create_offscreen_target(&device, size, OFFSCREEN_FORMAT);
+
+        Ok(Renderer::with_target(
+            device,
+            queue,
+            Target::Offscreen { texture, readback },
+            OFFSCREEN_FORMAT,
+            size,
+        ))
+    }
+
+    fn with_target(
+        device: Device,
+        queue: Queue,
+        target: Target<'r>,
+        format: TextureFormat,
+        size: PhysicalSize<u32>,
+    ) -> Self {
         let sampler = device.create_sampler(&SamplerDescriptor {
             label: Some("Texture Sampler"),
             address_mode_u: AddressMode::ClampToEdge,
@@ -95,28 +375,50 @@ impl<'r> Renderer<'r> {
             ..Default::default()
         });
 
+        // Up to three planes; unused slots repeat the first plane.
+        let plane_entry = |binding| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::FRAGMENT,
+            ty: BindingType::Texture {
+                sample_type: TextureSampleType::Float { filterable: true },
+                view_dimension: TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+
         let bind_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
             label: Some("Texture Bind Group Layout"),
             entries: &[
+                plane_entry(0),
+                plane_entry(1),
+                plane_entry(2),
                 BindGroupLayoutEntry {
-                    binding: 0,
+                    binding: 3,
                     visibility: ShaderStages::FRAGMENT,
-                    ty: BindingType::Texture {
-                        sample_type: TextureSampleType::Float { filterable: true },
-                        view_dimension: TextureViewDimension::D2,
-                        multisampled: false,
-                    },
+                    ty: BindingType::Sampler(SamplerBindingType::Filtering),
                     count: None,
                 },
                 BindGroupLayoutEntry {
-                    binding: 1,
+                    binding: 4,
                     visibility: ShaderStages::FRAGMENT,
-                    ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
                     count: None,
                 },
             ],
         });
 
+        let params = device.create_buffer(&BufferDescriptor {
+            label: Some("Shader Params"),
+            size: ShaderParams::SIZE,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
         let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
             label: Some("Render Pipeline Layout"),
             bind_group_layouts: &[&bind_layout],
@@ -140,7 +442,7 @@ impl<'r> Renderer<'r> {
             fragment: Some(FragmentState {
                 module: &shader,
                 entry_point: Some("fs_main"),
-                targets: &[Some(surface_format.into())],
+                targets: &[Some(format.into())],
                 compilation_options: PipelineCompilationOptions::default(),
             }),
             primitive: PrimitiveState::default(),
@@ -150,22 +452,28 @@ impl<'r> Renderer<'r> {
             cache: None,
         });
 
-        Ok(Self {
-            surface,
+        Self {
+            target,
+            format,
             device,
             queue,
-            config,
             sampler,
             pipeline,
             bind_layout,
+            params,
+            tone_mapping: ToneMapping::default(),
+            rotation: Rotation::None,
+            flip: false,
             frame_tex: None,
             surf_w: size.width.max(1),
             surf_h: size.height.max(1),
             vid_w: 0,
             vid_h: 0,
-        })
+        }
     }
 
+    /// Size the video is letterboxed to, as shown upright (`Frame::display_size`),
+    /// before the manual rotation of `set_orientation`.
     pub fn set_video_size(&mut self, width: u32, height: u32) {
         self.vid_w = width;
         self.vid_h = height;
@@ -173,16 +481,60 @@ impl<'r> Renderer<'r> {
 
     pub fn resize(&mut self, new_size: PhysicalSize<u32>) {
         if new_size.width > 0 && new_size.height > 0 {
-            self.config.width = new_size.width;
-            self.config.height = new_size.height;
-            self.surface.configure(&self.device, &self.config);
+            match &mut self.target {
+                Target::Window { surface, config } => {
+                    config.width = new_size.width;
+                    config.height = new_size.height;
+                    surface.configure(&self.device, config);
+                }
+                Target::Offscreen { texture, readback } => {
+                    (*texture, *readback) =
+                        create_offscreen_target(&self.device, new_size, self.format);
+                }
+            }
             self.surf_w = new_size.width;
             self.surf_h = new_size.height;
         }
     }
 
+    /// Choose how HDR content is mapped to SDR. Has no effect on SDR content.
+    pub fn set_tone_mapping(&mut self, tone_mapping: ToneMapping) {
+        self.tone_mapping = tone_mapping;
+        self.write_params();
+    }
+
+    pub fn tone_mapping(&self) -> ToneMapping {
+        self.tone_mapping
+    }
+
+    /// Turn the picture by `rotation` on top of the frame's own rotation, and
+    /// mirror it horizontally when `flip`.
+    pub fn set_orientation(&mut self, rotation: Rotation, flip: bool) {
+        self.rotation = rotation;
+        self.flip = flip;
+        self.write_params();
+    }
+
+    pub fn orientation(&self) -> (Rotation, bool) {
+        (self.rotation, self.flip)
+    }
+
+    fn write_params(&self) {
+        if let Some(tex) = &self.frame_tex {
+            let rotation = tex.rotation.then(self.rotation);
+            let params = ShaderParams::new(
+                tex.format,
+                tex.color,
+                self.tone_mapping,
+                rotation,
+                self.flip,
+            );
+            self.queue.write_buffer(&self.params, 0, &params.to_bytes());
+        }
+    }
+
     #[inline]
-    fn create_texture(&self, width: u32, height: u32) -> Texture {
+    fn create_texture(&self, width: u32, height: u32, format: TextureFormat) -> Texture {
         self.device.create_texture(&TextureDescriptor {
             label: Some("Frame Texture"),
             size: Extent3d {
@@ -193,70 +545,171 @@ impl<'r> Renderer<'r> {
             mip_level_count: 1,
             sample_count: 1,
             dimension: TextureDimension::D2,
-            format: TextureFormat::Rgba8UnormSrgb,
+            format,
             usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
             view_formats: &[],
         })
     }
 
-    pub fn set_frame_data(&mut self, width: u32, height: u32, data: &[u8]) {
+    pub fn set_frame(&mut self, frame: &Frame) {
+        let (width, height, format, color) = (frame.width, frame.height, frame.format, frame.color);
+
         let recreate = match &self.frame_tex {
-            Some(tex) => tex.width != width || tex.height != height,
+            Some(tex) => tex.width != width || tex.height != height || tex.format != format,
             None => true,
         };
 
         if recreate {
-            let tex = Self::create_texture(self, width, height);
+            let planes: Vec<Texture> = plane_layout(format)
+                .iter()
+                .map(|&(tex_format, shift)| {
+                    let (w, h) = subsampled(width, height, shift);
+                    self.create_texture(w, h, tex_format)
+                })
+                .collect();
+
+            let views: Vec<_> = planes
+                .iter()
+                .map(|tex| tex.create_view(&TextureViewDescriptor::default()))
+                .collect();
+            let view = |i: usize| BindingResource::TextureView(views.get(i).unwrap_or(&views[0]));
 
-            let view = tex.create_view(&TextureViewDescriptor::default());
             let bind = self.device.create_bind_group(&BindGroupDescriptor {
                 label: Some("Frame Texture Bind Group"),
                 layout: &self.bind_layout,
                 entries: &[
                     BindGroupEntry {
                         binding: 0,
-                        resource: BindingResource::TextureView(&view),
+                        resource: view(0),
                     },
                     BindGroupEntry {
                         binding: 1,
+                        resource: view(1),
+                    },
+                    BindGroupEntry {
+                        binding: 2,
+                        resource: view(2),
+                    },
+                    BindGroupEntry {
+                        binding: 3,
                         resource: BindingResource::Sampler(&self.sampler),
                     },
+                    BindGroupEntry {
+                        binding: 4,
+                        resource: self.params.as_entire_binding(),
+                    },
                 ],
             });
 
+            let staging_size = plane_layout(format)
+                .iter()
+                .map(|&(tex_format, shift)| {
+                    let (w, h) = subsampled(width, height, shift);
+                    aligned_bytes_per_row(tex_format, w) as u64 * h as u64
+                })
+                .sum();
+            let staging = self.device.create_buffer(&BufferDescriptor {
+                label: Some("Frame Staging Buffer"),
+                size: staging_size,
+                usage: BufferUsages::COPY_SRC | BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+
             self.frame_tex = Some(FrameTexture {
-                texture: tex,
+                planes,
+                staging,
                 bind,
                 width,
                 height,
+                format,
+                color,
+                rotation: frame.rotation,
             });
         }
 
-        if let Some(frame_tex) = &self.frame_tex {
-            self.queue.write_texture(
-                TexelCopyTextureInfo {
-                    texture: &frame_tex.texture,
-                    mip_level: 0,
-                    origin: Origin3d::ZERO,
-                    aspect: TextureAspect::All,
-                },
-                data,
-                TexelCopyBufferLayout {
-                    offset: 0,
-                    bytes_per_row: Some(4 * width),
-                    rows_per_image: Some(height),
-                },
-                Extent3d {
-                    width,
-                    height,
-                    depth_or_array_layers: 1,
-                },
-            );
+        if let Some(frame_tex) = self.frame_tex.as_mut() {
+            if recreate || frame_tex.color != color || frame_tex.rotation != frame.rotation {
+                frame_tex.color = color;
+                frame_tex.rotation = frame.rotation;
+                let rotation = frame.rotation.then(self.rotation);
+                let params =
+                    ShaderParams::new(format, color, self.tone_mapping, rotation, self.flip);
+                self.queue.write_buffer(&self.params, 0, &params.to_bytes());
+            }
+
+            let mut encoder = self
+                .device
+                .create_command_encoder(&CommandEncoderDescriptor {
+                    label: Some("Frame Upload Encoder"),
+                });
+            let mut offset = 0;
+            let rows = format.plane_sizes(width, height);
+            let layout = frame_tex.planes.iter().zip(plane_layout(format));
+            for (i, (texture, &(tex_format, shift))) in layout.enumerate() {
+                let (w, h) = subsampled(width, height, shift);
+                // Row size in the frame, which can differ from the texture's (RGB24)
+                let row = rows[i].0;
+                let stride = frame.stride[i];
+                // A stride shorter than a row would read past the plane
+                let Some(plane) = frame.plane(i).filter(|_| stride >= row) else {
+                    break;
+                };
+
+                let bytes_per_row = aligned_bytes_per_row(tex_format, w);
+                let size = bytes_per_row as u64 * h as u64;
+                if format != PixelFormat::RGB24 && stride == bytes_per_row as usize {
+                    self.queue.write_buffer(&frame_tex.staging, offset, plane);
+                } else if let Some(mut staging) = NonZeroU64::new(size).and_then(|size| {
+                    self.queue
+                        .write_buffer_with(&frame_tex.staging, offset, size)
+                }) {
+                    repack_rows(
+                        format,
+                        plane,
+                        stride,
+                        row,
+                        staging.as_mut(),
+                        bytes_per_row as usize,
+                    );
+                }
+
+                encoder.copy_buffer_to_texture(
+                    TexelCopyBufferInfo {
+                        buffer: &frame_tex.staging,
+                        layout: TexelCopyBufferLayout {
+                            offset,
+                            bytes_per_row: Some(bytes_per_row),
+                            rows_per_image: Some(h),
+                        },
+                    },
+                    TexelCopyTextureInfo {
+                        texture,
+                        mip_level: 0,
+                        origin: Origin3d::ZERO,
+                        aspect: TextureAspect::All,
+                    },
+                    Extent3d {
+                        width: w,
+                        height: h,
+                        depth_or_array_layers: 1,
+                    },
+                );
+                offset += size;
+            }
+            self.queue.submit(Some(encoder.finish()));
         }
     }
 
     pub fn render(&mut self) -> Result<(), Box<dyn Error>> {
-        let frame = match self.surface.get_current_texture() {
+        let surface = match &self.target {
+            Target::Window { surface, .. } => surface,
+            Target::Offscreen { texture, .. } => {
+                self.draw(&texture.create_view(&TextureViewDescriptor::default()));
+                return Ok(());
+            }
+        };
+
+        let frame = match surface.get_current_texture() {
             Ok(frame) => frame,
             Err(SurfaceError::Lost) => {
                 self.resize(PhysicalSize::new(self.surf_w, self.surf_h));
@@ -273,7 +726,53 @@ impl<'r> Renderer<'r> {
 
         let texture_desc = TextureViewDescriptor::default();
         let frame_view = frame.texture.create_view(&texture_desc);
+        self.draw(&frame_view);
+        frame.present();
+
+        Ok(())
+    }
+
+    /// Render and read the result back as tightly packed, sRGB-encoded RGBA
+    /// rows. Only available on renderers created with `new_headless`.
+    pub fn render_to_rgba(&mut self) -> Result<Vec<u8>, Box<dyn Error>> {
+        self.render()?;
+
+        let Target::Offscreen { texture, readback } = &self.target else {
+            return Err("Renderer draws to a window surface".into());
+        };
+        self.read_back(texture, readback)
+    }
+
+    /// Copy the image `render` presents, letterbox included, back to the CPU
+    /// at the surface size.
+    pub fn capture(&self) -> Result<CapturedImage, Box<dyn Error>> {
+        let bgra = match self.format {
+            TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => false,
+            TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb => true,
+            format => return Err(format!("Cannot capture a {format:?} surface").into()),
+        };
+
+        // Surface textures are usually not copyable → draw the same image
+        // again into one that is, and read it back through a staging buffer.
+        let size = PhysicalSize::new(self.surf_w, self.surf_h);
+        let (texture, staging) = create_offscreen_target(&self.device, size, self.format);
+        self.draw(&texture.create_view(&TextureViewDescriptor::default()));
+
+        let mut rgba = self.read_back(&texture, &staging)?;
+        if bgra {
+            for pixel in rgba.chunks_exact_mut(4) {
+                pixel.swap(0, 2);
+            }
+        }
+
+        Ok(CapturedImage {
+            width: size.width,
+            height: size.height,
+            rgba,
+        })
+    }
 
+    fn draw(&self, view: &TextureView) {
         let command_encoder_desc = CommandEncoderDescriptor {
             label: Some("Render Encoder"),
         };
@@ -283,7 +782,7 @@ impl<'r> Renderer<'r> {
         {
             let rp_color_attachment = RenderPassColorAttachment {
                 depth_slice: None,
-                view: &frame_view,
+                view,
                 resolve_target: None,
                 ops: Operations {
                     load: LoadOp::Clear(Color {
@@ -322,9 +821,57 @@ impl<'r> Renderer<'r> {
         }
 
         self.queue.submit(Some(command_encoder.finish()));
-        frame.present();
+    }
 
-        Ok(())
+    /// Copy a 4-byte-per-pixel `texture` into `readback` and return it without row padding.
+    fn read_back(&self, texture: &Texture, readback: &Buffer) -> Result<Vec<u8>, Box<dyn Error>> {
+        let (width, height) = (texture.width(), texture.height());
+        let padded_row = padded_bytes_per_row(width);
+
+        let mut command_encoder = self
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor {
+                label: Some("Readback Encoder"),
+            });
+        command_encoder.copy_texture_to_buffer(
+            TexelCopyTextureInfo {
+                texture,
+                mip_level: 0,
+                origin: Origin3d::ZERO,
+                aspect: TextureAspect::All,
+            },
+            TexelCopyBufferInfo {
+                buffer: readback,
+                layout: TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+        self.queue.submit(Some(command_encoder.finish()));
+
+        let slice = readback.slice(..);
+        let (tx, rx) = mpsc::channel();
+        slice.map_async(MapMode::Read, move |result| {
+            let _ = tx.send(result);
+        });
+        self.device.poll(PollType::Wait)?;
+        rx.recv()??;
+
+        let row = 4 * width as usize;
+        let mut pixels = Vec::with_capacity(row * height as usize);
+        for line in slice.get_mapped_range().chunks(padded_row as usize) {
+            pixels.extend_from_slice(&line[..row]);
+        }
+        readback.unmap();
+
+        Ok(pixels)
     }
 
     fn compute_letterbox_rect(&self) -> (u32, u32, u32, u32) {
@@ -335,8 +882,10 @@ impl<'r> Renderer<'r> {
 
         let sw = self.surf_w as f32;
         let sh = self.surf_h as f32;
-        let vw = self.vid_w as f32;
-        let vh = self.vid_h as f32;
+        let (vw, vh) = match self.rotation.is_transposed() {
+            true => (self.vid_h as f32, self.vid_w as f32),
+            false => (self.vid_w as f32, self.vid_h as f32),
+        };
 
         let surf_ar = sw / sh;
         let video_ar = vw / vh;
@@ -354,3 +903,96 @@ impl<'r> Renderer<'r> {
         }
     }
 }
+
+/// Size of a plane subsampled by `1 << shift`, rounding up.
+#[inline]
+fn subsampled(width: u32, height: u32, shift: u32) -> (u32, u32) {
+    let round = (1 << shift) - 1;
+    ((width + round) >> shift, (height + round) >> shift)
+}
+
+async fn request_device(adapter: &Adapter) -> Result<(Device, Queue), Box<dyn Error>> {
+    let device_desc = DeviceDescriptor {
+        label: Some("GPU Device"),
+        ..Default::default()
+    };
+
+    let device = adapter
+        .request_device(&device_desc)
+        .await
+        .map_err(|e| format!("Failed to create device: {}", e))?;
+    Ok(device)
+}
+
+/// Copyable color target and the staging buffer it is read back through.
+fn create_offscreen_target(
+    device: &Device,
+    size: PhysicalSize<u32>,
+    format: TextureFormat,
+) -> (Texture, Buffer) {
+    let texture = device.create_texture(&TextureDescriptor {
+        label: Some("Offscreen Target"),
+        size: Extent3d {
+            width: size.width,
+            height: size.height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: TextureDimension::D2,
+        format,
+        usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC,
+        view_formats: &[],
+    });
+
+    let readback = device.create_buffer(&BufferDescriptor {
+        label: Some("Readback Buffer"),
+        size: padded_bytes_per_row(size.width) as u64 * size.height as u64,
+        usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+
+    (texture, readback)
+}
+
+/// Texture → buffer copies need rows aligned to `COPY_BYTES_PER_ROW_ALIGNMENT`.
+#[inline]
+fn padded_bytes_per_row(width: u32) -> u32 {
+    (4 * width).next_multiple_of(COPY_BYTES_PER_ROW_ALIGNMENT)
+}
+
+/// Buffer → texture copies need the same alignment.
+#[inline]
+fn aligned_bytes_per_row(format: TextureFormat, width: u32) -> u32 {
+    let row = format.block_copy_size(None).unwrap_or(4) * width;
+    row.next_multiple_of(COPY_BYTES_PER_ROW_ALIGNMENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repack_rgb24_expands_padded_rows() {
+        // 2x2 RGB24, rows padded from 6 to 8 bytes; RGBA rows 256 bytes apart
+        let src = [
+            1, 2, 3, 4, 5, 6, 0xEE, 0xEE, 7, 8, 9, 10, 11, 12, 0xEE, 0xEE,
+        ];
+        let mut dst = vec![0; 2 * 256];
+        repack_rows(PixelFormat::RGB24, &src, 8, 6, &mut dst, 256);
+
+        assert_eq!(dst[..8], [1, 2, 3, 255, 4, 5, 6, 255]);
+        assert_eq!(dst[256..264], [7, 8, 9, 255, 10, 11, 12, 255]);
+        assert!(dst[8..256].iter().chain(&dst[264..]).all(|&b| b == 0));
+    }
+
+    #[test]
+    fn repack_copies_other_formats_row_by_row() {
+        let src = [1, 2, 3, 0xEE, 4, 5, 6, 0xEE];
+        let mut dst = vec![0; 2 * 256];
+        repack_rows(PixelFormat::GRAY8, &src, 4, 3, &mut dst, 256);
+
+        assert_eq!(dst[..4], [1, 2, 3, 0]);
+        assert_eq!(dst[256..260], [4, 5, 6, 0]);
+    }
+}
@@ -18,10 +18,215 @@ fn vs_main(@builtin(vertex_index) vidx : u32) -> VsOut {
   return out;
 }
 
+// Mirrors `ShaderParams` in renderer.rs
+struct Params {
+  format : u32,
+  transfer : u32,
+  primaries : u32,
+  tone_mapping : u32,
+  // YUV → RGB for the frame's matrix and range; offsets in the last column
+  yuv_to_rgb : mat4x4<f32>,
+  // Content peak in cd/m², for tone mapping
+  peak_luminance : f32,
+  // Clockwise quarter turns, then a horizontal mirror if non-zero
+  rotation : u32,
+  flip : u32,
+};
+
+const FORMAT_RGBA : u32 = 0u;
+const FORMAT_YUV420P : u32 = 1u;
+const FORMAT_NV12 : u32 = 2u;
+const FORMAT_P010 : u32 = 3u;
+const FORMAT_GRAY8 : u32 = 4u;
+
+const TRANSFER_SDR : u32 = 0u;
+const TRANSFER_LINEAR : u32 = 1u;
+const TRANSFER_PQ : u32 = 2u;
+const TRANSFER_HLG : u32 = 3u;
+
+const PRIMARIES_BT2020 : u32 = 1u;
+
+const TONE_MAPPING_BT2390 : u32 = 0u;
+const TONE_MAPPING_HABLE : u32 = 1u;
+const TONE_MAPPING_REINHARD : u32 = 2u;
+
+// SDR reference white in cd/m² (ITU-R BT.2408)
+const SDR_WHITE : f32 = 203.0;
+
+// Linear-light BT.2020 → BT.709 primaries (columns)
+const BT2020_TO_BT709 = mat3x3<f32>(
+    vec3<f32>( 1.6605, -0.1246, -0.0182),
+    vec3<f32>(-0.5876,  1.1329, -0.1006),
+    vec3<f32>(-0.0728, -0.0083,  1.1187)
+);
+
+// One texture per plane; unused ones alias tex0
 @group(0) @binding(0) var tex0 : texture_2d<f32>;
-@group(0) @binding(1) var samp : sampler;
+@group(0) @binding(1) var tex1 : texture_2d<f32>;
+@group(0) @binding(2) var tex2 : texture_2d<f32>;
+@group(0) @binding(3) var samp : sampler;
+@group(0) @binding(4) var<uniform> params : Params;
+
+fn yuv_to_rgb(yuv : vec3<f32>) -> vec3<f32> {
+  let rgb = (params.yuv_to_rgb * vec4<f32>(yuv, 1.0)).rgb;
+  return clamp(rgb, vec3<f32>(0.0), vec3<f32>(1.0));
+}
+
+// P010 sample stored as (low byte, high byte) → 10-bit code value in 0..1
+fn unpack_p010(lo_hi : vec2<f32>) -> f32 {
+  return 255.0 * (lo_hi.y * 256.0 + lo_hi.x) / 65472.0;
+}
+
+// SDR code values → linear light, which the sRGB surface re-encodes
+fn srgb_to_linear(c : vec3<f32>) -> vec3<f32> {
+  let lo = c / 12.92;
+  let hi = pow((c + 0.055) / 1.055, vec3<f32>(2.4));
+  return select(hi, lo, c <= vec3<f32>(0.04045));
+}
+
+// Inverse of `srgb_to_linear`
+fn linear_to_srgb(c : vec3<f32>) -> vec3<f32> {
+  let lo = c * 12.92;
+  let hi = 1.055 * pow(c, vec3<f32>(1.0 / 2.4)) - 0.055;
+  return select(hi, lo, c <= vec3<f32>(0.0031308));
+}
+
+// SMPTE ST 2084 constants
+const PQ_M1 : f32 = 0.1593017578125;
+const PQ_M2 : f32 = 78.84375;
+const PQ_C1 : f32 = 0.8359375;
+const PQ_C2 : f32 = 18.8515625;
+const PQ_C3 : f32 = 18.6875;
+
+// PQ signal → cd/m²
+fn pq_eotf(e : f32) -> f32 {
+  let p = pow(max(e, 0.0), 1.0 / PQ_M2);
+  return 10000.0 * pow(max(p - PQ_C1, 0.0) / (PQ_C2 - PQ_C3 * p), 1.0 / PQ_M1);
+}
+
+// cd/m² → PQ signal
+fn pq_inverse_eotf(nits : f32) -> f32 {
+  let y = pow(max(nits, 0.0) / 10000.0, PQ_M1);
+  return pow((PQ_C1 + PQ_C2 * y) / (1.0 + PQ_C3 * y), PQ_M2);
+}
+
+// HLG signal → cd/m², including the OOTF for a display at `peak_luminance`
+fn hlg_to_nits(e : vec3<f32>) -> vec3<f32> {
+  let a = 0.17883277;
+  let b = 0.28466892;
+  let c = 0.55991073;
+  let lo = e * e / 3.0;
+  let hi = (exp((e - c) / a) + b) / 12.0;
+  let scene = select(hi, lo, e <= vec3<f32>(0.5));
+  let ys = dot(scene, vec3<f32>(0.2627, 0.6780, 0.0593));
+  let gamma = 1.2 + 0.42 * log(params.peak_luminance / 1000.0) / log(10.0);
+  return params.peak_luminance * pow(max(ys, 1e-6), gamma - 1.0) * scene;
+}
+
+fn hable(x : f32) -> f32 {
+  let a = 0.15;
+  let b = 0.50;
+  let c = 0.10;
+  let d = 0.20;
+  let e = 0.02;
+  let f = 0.30;
+  return (x * (a * x + c * b) + d * e) / (x * (a * x + b) + d * f) - e / f;
+}
+
+// ITU-R BT.2390 EETF: Hermite roll-off above the knee, in the PQ domain.
+fn bt2390(nits : f32, peak : f32) -> f32 {
+  let src_peak = pq_inverse_eotf(peak);
+  let e = pq_inverse_eotf(nits) / src_peak;
+  let max_lum = pq_inverse_eotf(SDR_WHITE) / src_peak;
+  let ks = 1.5 * max_lum - 0.5;
+
+  var out = e;
+  if (e > ks) {
+    let t = (e - ks) / (1.0 - ks);
+    let t2 = t * t;
+    let t3 = t2 * t;
+    out = (2.0 * t3 - 3.0 * t2 + 1.0) * ks
+        + (t3 - 2.0 * t2 + t) * (1.0 - ks)
+        + (-2.0 * t3 + 3.0 * t2) * max_lum;
+  }
+  return pq_eotf(out * src_peak) / SDR_WHITE;
+}
+
+// Linear cd/m² → linear SDR with 1.0 at reference white. Scales all channels
+// by the curve applied to the brightest one, which keeps hues intact.
+fn tone_map(nits : vec3<f32>) -> vec3<f32> {
+  let peak = max(params.peak_luminance, SDR_WHITE);
+  let m = max(max(nits.r, nits.g), max(nits.b, 1e-6));
+  let x = m / SDR_WHITE;
+  let w = peak / SDR_WHITE;
+
+  var mapped = min(x, 1.0);
+  if (w > 1.0) {
+    if (params.tone_mapping == TONE_MAPPING_HABLE) {
+      mapped = hable(x) / hable(w);
+    } else if (params.tone_mapping == TONE_MAPPING_REINHARD) {
+      mapped = x * (1.0 + x / (w * w)) / (1.0 + x);
+    } else {
+      mapped = bt2390(m, peak);
+    }
+  }
+  return nits / SDR_WHITE * (min(mapped, 1.0) / x);
+}
+
+// Screen → texture coordinates: undo the mirror, then the rotation
+fn orient(screen : vec2<f32>) -> vec2<f32> {
+  var uv = screen;
+  if (params.flip != 0u) {
+    uv.x = 1.0 - uv.x;
+  }
+  switch params.rotation {
+    case 1u: { return vec2<f32>(uv.y, 1.0 - uv.x); }
+    case 2u: { return vec2<f32>(1.0 - uv.x, 1.0 - uv.y); }
+    case 3u: { return vec2<f32>(1.0 - uv.y, uv.x); }
+    default: { return uv; }
+  }
+}
 
 @fragment
 fn fs_main(@location(0) uv_in : vec2<f32>) -> @location(0) vec4<f32> {
-  return textureSample(tex0, samp, uv_in);
+  let uv = orient(uv_in);
+  let t0 = textureSample(tex0, samp, uv);
+  let t1 = textureSample(tex1, samp, uv);
+  let t2 = textureSample(tex2, samp, uv);
+
+  // Code values of the frame's transfer function, e.g. PQ for HDR10
+  var rgb : vec3<f32>;
+  if (params.format == FORMAT_RGBA) {
+    // The texture is sRGB, so sampling decoded them → encode again
+    rgb = linear_to_srgb(t0.rgb);
+  } else if (params.format == FORMAT_GRAY8) {
+    rgb = vec3<f32>(t0.r);
+  } else {
+    var yuv = vec3<f32>(t0.r, t1.r, t2.r);
+    if (params.format == FORMAT_NV12) {
+      yuv = vec3<f32>(t0.r, t1.r, t1.g);
+    } else if (params.format == FORMAT_P010) {
+      yuv = vec3<f32>(unpack_p010(t0.rg), unpack_p010(t1.rg), unpack_p010(t1.ba));
+    }
+    rgb = yuv_to_rgb(yuv);
+  }
+
+  var linear : vec3<f32>;
+  if (params.transfer == TRANSFER_LINEAR) {
+    linear = rgb;
+  } else if (params.transfer == TRANSFER_PQ) {
+    linear = vec3<f32>(pq_eotf(rgb.r), pq_eotf(rgb.g), pq_eotf(rgb.b));
+  } else if (params.transfer == TRANSFER_HLG) {
+    linear = hlg_to_nits(rgb);
+  } else {
+    linear = srgb_to_linear(rgb);
+  }
+
+  if (params.primaries == PRIMARIES_BT2020) {
+    linear = max(BT2020_TO_BT709 * linear, vec3<f32>(0.0));
+  }
+  if (params.transfer == TRANSFER_PQ || params.transfer == TRANSFER_HLG) {
+    linear = tone_map(linear);
+  }
+  return vec4<f32>(clamp(linear, vec3<f32>(0.0), vec3<f32>(1.0)), 1.0);
 }
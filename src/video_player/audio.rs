@@ -0,0 +1,349 @@
+#[cfg(feature = "audio-device")]
+use crate::video_player::device_sink::DeviceSink;
+use crate::video_player::{
+    audio_sink::{AudioSink, AudioSpec, NullSink, WavSink},
+    clock::PlaybackClock,
+    decoder::pts_to_us,
+    error::PlayerError,
+    tempo::Tempo,
+    AudioOutput,
+};
+use ffmpeg::{
+    codec::context::Context,
+    decoder,
+    ffi::{swr_get_delay, swr_get_out_samples},
+    format::{self, context::Input, sample::Type as SampleType},
+    media::Type,
+    software::resampling::Context as Resampler,
+    util::frame::Audio,
+    ChannelLayout, Packet, Rational, Stream,
+};
+use ffmpeg_next as ffmpeg;
+use std::{collections::VecDeque, time::Duration};
+
+/// Clock corrections smaller than this are ignored to keep video pacing smooth.
+const AV_SYNC_THRESHOLD_US: u64 = 20_000;
+
+/// Decodes the best audio stream and feeds it to an `AudioSink`.
+///
+/// While audio is playing its position is the master clock: `sync_clock`
+/// pulls the shared `PlaybackClock` onto it. At rates other than 1x it is
+/// time-stretched to match, keeping its pitch.
+pub struct AudioTrack {
+    index: usize,
+    dec: decoder::Audio,
+    resampler: Option<Resampler>,
+    /// Time-stretches the resampled audio while the rate is not 1x.
+    tempo: Option<Tempo>,
+    sink: Box<dyn AudioSink>,
+    spec: AudioSpec,
+    time_base: Rational,
+    /// Sample frames handed to the sink since it was opened.
+    written_frames: u64,
+    /// `(written_frames, ts_us)` at the start of each queued chunk, oldest first.
+    segments: VecDeque<(u64, u64)>,
+    /// Audio before this timestamp is dropped (frame-accurate seek).
+    skip_until_us: Option<u64>,
+    /// Added to sample timestamps, so they follow the video's timeline across loops.
+    timeline_offset_us: u64,
+    paused: bool,
+    /// Playback rate the queued audio was stretched for.
+    rate: f64,
+}
+
+impl AudioTrack {
+    /// Open the best audio stream of `ictx`, or `None` if there is none or audio is disabled.
+    pub fn open(ictx: &Input, output: AudioOutput) -> Result<Option<Self>, PlayerError> {
+        let Some(astream) = ictx.streams().best(Type::Audio) else {
+            return Ok(None);
+        };
+
+        let sink: Box<dyn AudioSink> = match output {
+            AudioOutput::Disabled => return Ok(None),
+            #[cfg(feature = "audio-device")]
+            AudioOutput::Device => match DeviceSink::open() {
+                Ok(sink) => Box::new(sink),
+                Err(e) => {
+                    eprintln!("Audio device unavailable ({e}), falling back to null sink");
+                    Box::new(NullSink::new(AudioSpec::default()))
+                }
+            },
+            #[cfg(not(feature = "audio-device"))]
+            AudioOutput::Device => Box::new(NullSink::new(AudioSpec::default())),
+            AudioOutput::Null => Box::new(NullSink::new(AudioSpec::default())),
+            AudioOutput::Wav(path) => Box::new(
+                WavSink::create(&path, AudioSpec::default())
+                    .map_err(|e| PlayerError::Audio(format!("{}: {e}", path.display())))?,
+            ),
+            AudioOutput::Custom(sink) => sink,
+        };
+
+        let dec = open_audio_decoder(&astream)?;
+
+        Ok(Some(Self {
+            index: astream.index(),
+            dec,
+            resampler: None,
+            tempo: None,
+            spec: sink.spec(),
+            sink,
+            time_base: astream.time_base(),
+            written_frames: 0,
+            segments: VecDeque::new(),
+            skip_until_us: None,
+            timeline_offset_us: 0,
+            paused: false,
+            rate: 1.0,
+        }))
+    }
+
+    /// Switch to the best audio stream of a reopened input, keeping the sink.
+    /// False if it has none.
+    pub fn reopen(&mut self, ictx: &Input) -> Result<bool, PlayerError> {
+        let Some(astream) = ictx.streams().best(Type::Audio) else {
+            return Ok(false);
+        };
+
+        self.dec = open_audio_decoder(&astream)?;
+        self.index = astream.index();
+        self.time_base = astream.time_base();
+        self.resampler = None;
+        self.tempo = None;
+        self.skip_until_us = None;
+        // Queued samples are from before the gap
+        self.drop_queued();
+        Ok(true)
+    }
+
+    #[inline]
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Decode one audio packet and queue the result on the sink.
+    pub fn decode(&mut self, packet: &Packet) {
+        if let Err(e) = self.dec.send_packet(packet) {
+            eprintln!("audio send_packet error: {e}");
+            return;
+        }
+        self.drain_decoder();
+    }
+
+    /// Discard everything queued before a seek. Timestamps from here on are
+    /// shifted by `timeline_offset_us`, as are the video's.
+    pub fn flush(&mut self, skip_until_us: Option<u64>, timeline_offset_us: u64) {
+        self.dec.flush();
+        // Drop samples buffered inside swresample and atempo as well.
+        self.resampler = None;
+        self.tempo = None;
+        self.drop_queued();
+        self.skip_until_us = skip_until_us;
+        self.timeline_offset_us = timeline_offset_us;
+    }
+
+    /// Decode the remaining audio and block until the sink has played it.
+    pub fn finish(&mut self, clock: &PlaybackClock, stop: impl Fn() -> bool) {
+        let _ = self.dec.send_eof();
+        self.drain_decoder();
+        if let Err(e) = self.flush_filters() {
+            eprintln!("Resampling error: {e}");
+        }
+
+        while !stop() && self.sink.played_frames() < self.written_frames {
+            self.sync_clock(clock);
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    /// Mirror the clock's pause state on the sink and pull the clock onto the audio position.
+    pub fn sync_clock(&mut self, clock: &PlaybackClock) {
+        let paused = clock.is_paused();
+        if paused != self.paused {
+            self.sink.set_paused(paused);
+            self.paused = paused;
+        }
+
+        // Queued audio was stretched for the old rate → start over at the new one
+        let rate = clock.rate();
+        if rate != self.rate {
+            self.drop_queued();
+            self.tempo = None;
+            self.rate = rate;
+        }
+
+        // Starved or paused → let the clock run on its own so video can't deadlock on audio.
+        let played = self.sink.played_frames();
+        if paused || played >= self.written_frames {
+            return;
+        }
+
+        if let Some(position_us) = self.position_us(played) {
+            if position_us.abs_diff(clock.now_us()) > AV_SYNC_THRESHOLD_US {
+                clock.set(position_us);
+            }
+        }
+    }
+
+    fn drop_queued(&mut self) {
+        self.sink.clear();
+        self.written_frames = self.sink.played_frames();
+        self.segments.clear();
+    }
+
+    /// Timestamp of the sample frame at `played`, offset like the video.
+    fn position_us(&mut self, played: u64) -> Option<u64> {
+        while self.segments.len() > 1 && self.segments[1].0 <= played {
+            self.segments.pop_front();
+        }
+
+        let &(start, ts_us) = self.segments.front()?;
+        let offset_us = (played.saturating_sub(start) as f64 * 1_000_000.0 * self.rate
+            / self.spec.sample_rate as f64) as u64;
+        Some(ts_us + offset_us)
+    }
+
+    fn drain_decoder(&mut self) {
+        let mut decoded = Audio::empty();
+        while self.dec.receive_frame(&mut decoded).is_ok() {
+            let ts_us = pts_to_us(
+                decoded.timestamp().unwrap_or(0),
+                self.time_base.0 as u32,
+                self.time_base.1 as u32,
+            )
+            .unwrap_or(0)
+            .max(0) as u64
+                + self.timeline_offset_us;
+
+            if let Some(target) = self.skip_until_us {
+                let duration_us =
+                    decoded.samples() as u64 * 1_000_000 / decoded.rate().max(1) as u64;
+                if ts_us + duration_us < target {
+                    continue;
+                }
+                self.skip_until_us = None;
+            }
+
+            if let Err(e) = self.resample_and_write(&decoded, ts_us) {
+                eprintln!("Resampling error: {e}");
+            }
+        }
+    }
+
+    fn resample_and_write(&mut self, decoded: &Audio, ts_us: u64) -> Result<(), ffmpeg::Error> {
+        if self.resampler.is_none() {
+            let mut layout = decoded.channel_layout();
+            if layout.channels() == 0 {
+                layout = ChannelLayout::default(decoded.channels() as i32);
+            }
+
+            self.resampler = Some(Resampler::get(
+                decoded.format(),
+                layout,
+                decoded.rate(),
+                format::Sample::F32(SampleType::Packed),
+                ChannelLayout::default(self.spec.channels as i32),
+                self.spec.sample_rate,
+            )?);
+        }
+
+        let Some(resampler) = self.resampler.as_mut() else {
+            return Ok(());
+        };
+        // Output starts with the samples swresample still holds from earlier input.
+        let delay_us = unsafe { swr_get_delay(resampler.as_mut_ptr(), 1_000_000) }.max(0) as u64;
+        let out = resample(resampler, Some(decoded))?;
+        let ts_us = ts_us.saturating_sub(delay_us);
+
+        if self.rate == 1.0 {
+            self.segments.push_back((self.written_frames, ts_us));
+            self.write(&out);
+            return Ok(());
+        }
+
+        // atempo output follows its input without gaps, so one segment from
+        // the first sample on covers all of it.
+        if self.tempo.is_none() {
+            self.tempo = Some(Tempo::new(self.rate, self.spec)?);
+            self.segments.push_back((self.written_frames, ts_us));
+        }
+        self.stretch(Some(&out))
+    }
+
+    /// Write out what swresample and atempo still buffer at end of stream. It
+    /// carries on from the last segment, so it needs no timestamp of its own.
+    fn flush_filters(&mut self) -> Result<(), ffmpeg::Error> {
+        if let Some(resampler) = self.resampler.as_mut() {
+            let out = resample(resampler, None)?;
+            match self.tempo.is_some() {
+                true => self.stretch(Some(&out))?,
+                false => self.write(&out),
+            }
+        }
+        self.stretch(None)
+    }
+
+    /// Run resampled audio (or the end of it, if `None`) through atempo and
+    /// write whatever comes out.
+    fn stretch(&mut self, input: Option<&Audio>) -> Result<(), ffmpeg::Error> {
+        // Nothing came out of swresample
+        if input.is_some_and(|input| input.samples() == 0) {
+            return Ok(());
+        }
+        let Some(tempo) = self.tempo.as_mut() else {
+            return Ok(());
+        };
+        let ready = tempo.run(input)?;
+        // Drained → a later stream of audio needs a fresh filter
+        if input.is_none() {
+            self.tempo = None;
+        }
+        for out in ready {
+            self.write(&out);
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, out: &Audio) {
+        let frames = out.samples();
+        if frames == 0 {
+            return;
+        }
+        let len = frames * self.spec.channels as usize;
+        let samples: Vec<f32> = out.data(0)[..len * 4]
+            .chunks_exact(4)
+            .map(|b| f32::from_ne_bytes([b[0], b[1], b[2], b[3]]))
+            .collect();
+
+        self.sink.write(&samples);
+        self.written_frames += frames as u64;
+    }
+}
+
+/// Convert `input` (or drain the buffered tail, if `None`) into a frame sized
+/// for everything swresample can return, so upsampling isn't truncated.
+fn resample(resampler: &mut Resampler, input: Option<&Audio>) -> Result<Audio, ffmpeg::Error> {
+    let in_samples = input.map_or(0, |input| input.samples());
+    let capacity = unsafe { swr_get_out_samples(resampler.as_mut_ptr(), in_samples as _) };
+    if capacity < 0 {
+        return Err(ffmpeg::Error::from(capacity));
+    }
+    if capacity == 0 {
+        return Ok(Audio::empty());
+    }
+
+    let output = *resampler.output();
+    let mut out = Audio::new(output.format, capacity as usize, output.channel_layout);
+    match input {
+        Some(input) => resampler.run(input, &mut out)?,
+        None => resampler.flush(&mut out)?,
+    };
+    Ok(out)
+}
+
+fn open_audio_decoder(stream: &Stream) -> Result<decoder::Audio, PlayerError> {
+    let parameters = stream.parameters();
+    let id = parameters.id();
+    Context::from_parameters(parameters)
+        .and_then(|ctx| ctx.decoder().audio())
+        .map_err(|e| PlayerError::decoder(id, e))
+}
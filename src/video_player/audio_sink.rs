@@ -0,0 +1,229 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Seek, SeekFrom, Write},
+    path::Path,
+    time::Instant,
+};
+
+/// Format the decoder resamples to before handing samples to a sink.
+/// Samples are always interleaved `f32`.
+#[derive(Clone, Copy, Debug)]
+pub struct AudioSpec {
+    pub sample_rate: u32,
+    pub channels: u16,
+}
+
+impl Default for AudioSpec {
+    fn default() -> Self {
+        Self {
+            sample_rate: 48_000,
+            channels: 2,
+        }
+    }
+}
+
+/// Destination for decoded audio.
+///
+/// The player reads `played_frames` back to drive the playback clock, so a sink
+/// should report samples as played only once they have actually been consumed.
+pub trait AudioSink {
+    fn spec(&self) -> AudioSpec;
+
+    /// Queue interleaved samples for playback. Should not block.
+    fn write(&mut self, samples: &[f32]);
+
+    /// Number of sample frames (one sample per channel) played out so far.
+    fn played_frames(&self) -> u64;
+
+    /// Stop or continue consuming queued samples.
+    fn set_paused(&mut self, paused: bool);
+
+    /// Drop queued samples that have not been played yet, e.g. after a seek.
+    fn clear(&mut self);
+}
+
+/// Tracks how far a sink without a device clock has "played" in real time.
+struct RealtimeCursor {
+    sample_rate: u32,
+    written: u64,
+    played_base: u64,
+    since: Option<Instant>,
+}
+
+impl RealtimeCursor {
+    fn new(sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            written: 0,
+            played_base: 0,
+            since: Some(Instant::now()),
+        }
+    }
+
+    fn played(&self) -> u64 {
+        let elapsed = match self.since {
+            Some(since) => since.elapsed().as_micros() as u64 * self.sample_rate as u64 / 1_000_000,
+            None => 0,
+        };
+        (self.played_base + elapsed).min(self.written)
+    }
+
+    fn advance(&mut self, frames: u64) {
+        // Don't count time spent starved of samples as playback.
+        if self.played() == self.written {
+            self.rebase(self.written);
+        }
+        self.written += frames;
+    }
+
+    fn set_paused(&mut self, paused: bool) {
+        if paused && self.since.is_some() {
+            self.played_base = self.played();
+            self.since = None;
+        } else if !paused && self.since.is_none() {
+            self.since = Some(Instant::now());
+        }
+    }
+
+    fn clear(&mut self) {
+        self.written = self.played();
+        self.rebase(self.written);
+    }
+
+    fn rebase(&mut self, played: u64) {
+        self.played_base = played;
+        if self.since.is_some() {
+            self.since = Some(Instant::now());
+        }
+    }
+}
+
+/// Discards samples at playback speed. Keeps A/V sync on machines without audio.
+pub struct NullSink {
+    spec: AudioSpec,
+    cursor: RealtimeCursor,
+}
+
+impl NullSink {
+    pub fn new(spec: AudioSpec) -> Self {
+        Self {
+            spec,
+            cursor: RealtimeCursor::new(spec.sample_rate),
+        }
+    }
+}
+
+impl AudioSink for NullSink {
+    fn spec(&self) -> AudioSpec {
+        self.spec
+    }
+
+    fn write(&mut self, samples: &[f32]) {
+        self.cursor
+            .advance((samples.len() / self.spec.channels as usize) as u64);
+    }
+
+    fn played_frames(&self) -> u64 {
+        self.cursor.played()
+    }
+
+    fn set_paused(&mut self, paused: bool) {
+        self.cursor.set_paused(paused);
+    }
+
+    fn clear(&mut self) {
+        self.cursor.clear();
+    }
+}
+
+/// Writes samples to a 32-bit float WAV file, paced like `NullSink`.
+pub struct WavSink {
+    spec: AudioSpec,
+    cursor: RealtimeCursor,
+    out: BufWriter<File>,
+    data_bytes: u32,
+}
+
+impl WavSink {
+    const HEADER_LEN: u32 = 44;
+
+    pub fn create(path: &Path, spec: AudioSpec) -> io::Result<Self> {
+        let mut out = BufWriter::new(File::create(path)?);
+        write_wav_header(&mut out, spec, 0)?;
+
+        Ok(Self {
+            spec,
+            cursor: RealtimeCursor::new(spec.sample_rate),
+            out,
+            data_bytes: 0,
+        })
+    }
+
+    /// Patch the RIFF and data chunk sizes now that the length is known.
+    fn finalize(&mut self) -> io::Result<()> {
+        self.out.seek(SeekFrom::Start(0))?;
+        write_wav_header(&mut self.out, self.spec, self.data_bytes)?;
+        self.out.seek(SeekFrom::End(0))?;
+        self.out.flush()
+    }
+}
+
+impl AudioSink for WavSink {
+    fn spec(&self) -> AudioSpec {
+        self.spec
+    }
+
+    fn write(&mut self, samples: &[f32]) {
+        for s in samples {
+            if let Err(e) = self.out.write_all(&s.to_le_bytes()) {
+                eprintln!("WAV write error: {e}");
+                return;
+            }
+        }
+        self.data_bytes = self
+            .data_bytes
+            .saturating_add((samples.len() * 4) as u32)
+            .min(u32::MAX - Self::HEADER_LEN);
+        self.cursor
+            .advance((samples.len() / self.spec.channels as usize) as u64);
+    }
+
+    fn played_frames(&self) -> u64 {
+        self.cursor.played()
+    }
+
+    fn set_paused(&mut self, paused: bool) {
+        self.cursor.set_paused(paused);
+    }
+
+    fn clear(&mut self) {
+        // Already on disk; only the pacing is reset.
+        self.cursor.clear();
+    }
+}
+
+impl Drop for WavSink {
+    fn drop(&mut self) {
+        if let Err(e) = self.finalize() {
+            eprintln!("WAV finalize error: {e}");
+        }
+    }
+}
+
+fn write_wav_header(out: &mut impl Write, spec: AudioSpec, data_bytes: u32) -> io::Result<()> {
+    const FORMAT_IEEE_FLOAT: u16 = 3;
+    let block_align = spec.channels * 4;
+
+    out.write_all(b"RIFF")?;
+    out.write_all(&(36 + data_bytes).to_le_bytes())?;
+    out.write_all(b"WAVEfmt ")?;
+    out.write_all(&16u32.to_le_bytes())?;
+    out.write_all(&FORMAT_IEEE_FLOAT.to_le_bytes())?;
+    out.write_all(&spec.channels.to_le_bytes())?;
+    out.write_all(&spec.sample_rate.to_le_bytes())?;
+    out.write_all(&(spec.sample_rate * block_align as u32).to_le_bytes())?;
+    out.write_all(&block_align.to_le_bytes())?;
+    out.write_all(&32u16.to_le_bytes())?;
+    out.write_all(b"data")?;
+    out.write_all(&data_bytes.to_le_bytes())
+}
@@ -0,0 +1,102 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+/// Playback position shared between the decoder thread and the presenter.
+///
+/// The clock advances with wall time (scaled by the playback rate) while playing
+/// and freezes while paused. Positions are on the stream timeline, i.e.
+/// comparable with `Frame::ts_us`.
+#[derive(Clone)]
+pub struct PlaybackClock {
+    state: Arc<Mutex<ClockState>>,
+}
+
+struct ClockState {
+    /// Stream position at `anchor`, in microseconds.
+    position_us: u64,
+    /// Wall time at which `position_us` was reached; `None` while paused.
+    anchor: Option<Instant>,
+    /// Stream microseconds per wall-clock microsecond.
+    rate: f64,
+}
+
+impl ClockState {
+    #[inline]
+    fn now_us(&self) -> u64 {
+        match self.anchor {
+            Some(anchor) => {
+                self.position_us + (anchor.elapsed().as_micros() as f64 * self.rate) as u64
+            }
+            None => self.position_us,
+        }
+    }
+}
+
+impl Default for PlaybackClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PlaybackClock {
+    pub fn new() -> Self {
+        Self {
+            state: Arc::new(Mutex::new(ClockState {
+                position_us: 0,
+                anchor: Some(Instant::now()),
+                rate: 1.0,
+            })),
+        }
+    }
+
+    /// Current playback position in microseconds.
+    pub fn now_us(&self) -> u64 {
+        self.state.lock().unwrap().now_us()
+    }
+
+    /// Freeze the clock at its current position.
+    pub fn pause(&self) {
+        let mut state = self.state.lock().unwrap();
+        if state.anchor.is_some() {
+            state.position_us = state.now_us();
+            state.anchor = None;
+        }
+    }
+
+    /// Let the clock advance again from where it was paused.
+    pub fn resume(&self) {
+        let mut state = self.state.lock().unwrap();
+        if state.anchor.is_none() {
+            state.anchor = Some(Instant::now());
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.state.lock().unwrap().anchor.is_none()
+    }
+
+    /// Change the playback speed without jumping the current position.
+    pub(crate) fn set_rate(&self, rate: f64) {
+        let mut state = self.state.lock().unwrap();
+        state.position_us = state.now_us();
+        if state.anchor.is_some() {
+            state.anchor = Some(Instant::now());
+        }
+        state.rate = rate;
+    }
+
+    pub fn rate(&self) -> f64 {
+        self.state.lock().unwrap().rate
+    }
+
+    /// Jump to `position_us`, keeping the paused/playing state.
+    pub(crate) fn set(&self, position_us: u64) {
+        let mut state = self.state.lock().unwrap();
+        state.position_us = position_us;
+        if state.anchor.is_some() {
+            state.anchor = Some(Instant::now());
+        }
+    }
+}
@@ -0,0 +1,83 @@
+/// YUV → RGB matrix coefficients.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ColorMatrix {
+    /// SD content, and the fallback for untagged video below 720 lines.
+    #[default]
+    Bt601,
+    Bt709,
+    /// Non-constant luminance BT.2020.
+    Bt2020,
+}
+
+impl ColorMatrix {
+    /// Luma weights `(Kr, Kb)`; `Kg` is the remainder.
+    pub fn luma_weights(self) -> (f32, f32) {
+        match self {
+            ColorMatrix::Bt601 => (0.299, 0.114),
+            ColorMatrix::Bt709 => (0.2126, 0.0722),
+            ColorMatrix::Bt2020 => (0.2627, 0.0593),
+        }
+    }
+}
+
+/// Which code values represent black and white.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ColorRange {
+    /// Studio swing: Y in 16..=235, chroma in 16..=240 (for 8 bits).
+    #[default]
+    Limited,
+    Full,
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ColorPrimaries {
+    #[default]
+    Bt709,
+    Bt601,
+    Bt2020,
+}
+
+/// How code values map to light.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TransferFunction {
+    /// SDR gamma (BT.709, BT.601 and sRGB are all displayed alike).
+    #[default]
+    Sdr,
+    Linear,
+    /// SMPTE ST 2084, used by HDR10.
+    Pq,
+    /// ARIB STD-B67 hybrid log-gamma.
+    Hlg,
+}
+
+/// Static HDR metadata: the mastering display (SMPTE ST 2086) and content
+/// light levels. Luminances are in cd/m².
+#[derive(Default, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct HdrMetadata {
+    pub min_luminance: Option<f32>,
+    pub max_luminance: Option<f32>,
+    /// Brightest pixel in the stream (MaxCLL).
+    pub max_content_light: Option<f32>,
+}
+
+impl HdrMetadata {
+    /// Brightest level the content is expected to reach, if known.
+    pub fn peak_luminance(&self) -> Option<f32> {
+        self.max_content_light.or(self.max_luminance)
+    }
+}
+
+/// Color description of a `Frame`.
+///
+/// For RGB pixel formats the matrix and range no longer apply: the decoder has
+/// already converted to full range RGB using them.
+#[derive(Default, Clone, Copy, PartialEq, Debug)]
+pub struct ColorInfo {
+    pub matrix: ColorMatrix,
+    pub range: ColorRange,
+    pub primaries: ColorPrimaries,
+    pub transfer: TransferFunction,
+    /// From the stream or the most recent frame that carried it.
+    pub hdr: Option<HdrMetadata>,
+}
@@ -0,0 +1,362 @@
+use crate::video_player::{
+    color::{ColorInfo, ColorMatrix, ColorPrimaries, ColorRange, HdrMetadata, TransferFunction},
+    error::PlayerError,
+    frame_buffer::Frame,
+    frame_pool::{FrameData, FramePool},
+    probe::stream_rotation,
+    OutputSize, PixelFormat, PlaybackParams, Rotation, ScaleFilter,
+};
+use ffmpeg::{
+    codec::packet,
+    decoder,
+    ffi::{
+        sws_getCoefficients, sws_setColorspaceDetails, SWS_CS_BT2020, SWS_CS_ITU601, SWS_CS_ITU709,
+    },
+    software::scaling::{Context as Scaler, Flags},
+    util::{
+        color::{Primaries, Range, Space, TransferCharacteristic},
+        format::Pixel,
+        frame::{self, Video},
+    },
+    Rational, Stream,
+};
+use ffmpeg_next as ffmpeg;
+use std::sync::Arc;
+
+/// Turns decoded frames into `Frame`s in the requested `PixelFormat` and size.
+///
+/// YUV output skips `swscale` entirely when the decoder already produces that
+/// layout at the output size; the planes are copied as-is and converted to RGB
+/// on the GPU.
+pub struct FrameConverter {
+    format: PixelFormat,
+    output_size: OutputSize,
+    filter: ScaleFilter,
+    // Layout and size of the decoded frames the scaler was set up for
+    input: Option<(Pixel, u32, u32)>,
+    // None → decoded frames are already in the output layout
+    scaler: Option<Scaler>,
+    // What the scaler was last told about its input
+    scaler_color: Option<ColorInfo>,
+    // Sticky: usually only keyframes carry it
+    hdr: Option<HdrMetadata>,
+    rotation: Rotation,
+    // The container's SAR, which overrides the codec's
+    stream_sar: Option<f64>,
+    out: Video,
+    pool: FramePool,
+}
+
+impl FrameConverter {
+    pub fn new(
+        stream: &Stream,
+        dec: &decoder::Video,
+        params: &PlaybackParams,
+        pool: FramePool,
+    ) -> Result<Self, PlayerError> {
+        // Don't squeeze 10-bit (HDR) sources into 8 bits.
+        let format = match params.pixel_format {
+            PixelFormat::Yuv420p | PixelFormat::Nv12 if bit_depth(dec.format()) > 8 => {
+                PixelFormat::P010
+            }
+            format => format,
+        };
+
+        let mut converter = Self {
+            format,
+            output_size: params.output_size,
+            filter: params.scale_filter,
+            input: None,
+            scaler: None,
+            scaler_color: None,
+            hdr: stream_hdr_metadata(stream),
+            rotation: Rotation::from_degrees(stream_rotation(stream).unwrap_or(0.0)),
+            stream_sar: aspect(unsafe { (*stream.as_ptr()).sample_aspect_ratio }.into()),
+            out: Video::empty(),
+            pool,
+        };
+        // Fail early if swscale can't handle the stream at all
+        converter
+            .prepare(dec.format(), dec.width(), dec.height())
+            .map_err(PlayerError::Scaler)?;
+        Ok(converter)
+    }
+
+    /// Set up the scaler for decoded frames of this layout and size, unless it already is.
+    fn prepare(&mut self, format: Pixel, width: u32, height: u32) -> Result<(), ffmpeg::Error> {
+        if self.input == Some((format, width, height)) {
+            return Ok(());
+        }
+
+        let (out_w, out_h) = self.output_size.resolve(width, height);
+        let passthrough = (out_w, out_h) == (width, height)
+            && match self.format {
+                PixelFormat::Yuv420p => matches!(format, Pixel::YUV420P | Pixel::YUVJ420P),
+                PixelFormat::Nv12 => format == Pixel::NV12,
+                PixelFormat::P010 => format == Pixel::P010LE,
+                output => format == ffmpeg_pixel(output),
+            };
+
+        self.scaler = match passthrough {
+            true => None,
+            false => Some(Scaler::get(
+                format,
+                width,
+                height,
+                ffmpeg_pixel(self.format),
+                out_w,
+                out_h,
+                scale_flags(self.filter),
+            )?),
+        };
+        self.scaler_color = None;
+        // Allocated by the scaler at the new output size
+        self.out = Video::empty();
+        self.input = Some((format, width, height));
+        Ok(())
+    }
+
+    pub fn convert(
+        &mut self,
+        decoded: &Video,
+        ts_us: u64,
+        stream_ts_us: u64,
+    ) -> Result<Arc<Frame>, ffmpeg::Error> {
+        // The size or layout can change mid-stream, e.g. across an HLS variant switch
+        self.prepare(decoded.format(), decoded.width(), decoded.height())?;
+
+        let mut color = color_info(decoded);
+        let rgb = !self.format.is_yuv();
+
+        let src = match self.scaler.as_mut() {
+            Some(scaler) => {
+                if self.scaler_color != Some(color) {
+                    set_colorspace(scaler, color, rgb);
+                    self.scaler_color = Some(color);
+                }
+                scaler.run(decoded, &mut self.out)?;
+                &self.out
+            }
+            None => decoded,
+        };
+
+        if rgb {
+            color.range = ColorRange::Full;
+        }
+        if let Some(hdr) = frame_hdr_metadata(decoded) {
+            self.hdr = Some(hdr);
+        }
+        color.hdr = self.hdr;
+
+        // Resizing to another aspect ratio changes the pixels' shape
+        let sar = self
+            .stream_sar
+            .or_else(|| aspect(decoded.aspect_ratio()))
+            .unwrap_or(1.0)
+            * (decoded.width() as f64 * src.height() as f64)
+            / (decoded.height() as f64 * src.width() as f64);
+
+        let (data, stride) = copy_planes(src, self.format, &self.pool);
+        Ok(Arc::new(Frame {
+            width: src.width(),
+            height: src.height(),
+            format: self.format,
+            color,
+            data,
+            stride,
+            rotation: self.rotation,
+            sar,
+            ts_us,
+            stream_ts_us,
+        }))
+    }
+}
+
+/// `ratio` as a number, unless FFmpeg marks it unknown (0/1 or 0/0).
+fn aspect(ratio: Rational) -> Option<f64> {
+    (ratio.numerator() > 0 && ratio.denominator() > 0).then(|| f64::from(ratio))
+}
+
+/// FFmpeg's name for the layout of `format`.
+pub(crate) fn ffmpeg_pixel(format: PixelFormat) -> Pixel {
+    match format {
+        PixelFormat::RGBA => Pixel::RGBA,
+        PixelFormat::RGB24 => Pixel::RGB24,
+        PixelFormat::BGRA => Pixel::BGRA,
+        PixelFormat::GRAY8 => Pixel::GRAY8,
+        PixelFormat::Yuv420p => Pixel::YUV420P,
+        PixelFormat::Nv12 => Pixel::NV12,
+        PixelFormat::P010 => Pixel::P010LE,
+    }
+}
+
+fn scale_flags(filter: ScaleFilter) -> Flags {
+    match filter {
+        ScaleFilter::Bilinear => Flags::BILINEAR,
+        ScaleFilter::Bicubic => Flags::BICUBIC,
+        ScaleFilter::Lanczos => Flags::LANCZOS,
+        ScaleFilter::Area => Flags::AREA,
+        ScaleFilter::Point => Flags::POINT,
+    }
+}
+
+/// Tell swscale how to interpret its input. YUV output keeps the input's matrix
+/// and range; RGB output is full range.
+pub(crate) fn set_colorspace(scaler: &mut Scaler, color: ColorInfo, rgb: bool) {
+    let colorspace = match color.matrix {
+        ColorMatrix::Bt601 => SWS_CS_ITU601,
+        ColorMatrix::Bt709 => SWS_CS_ITU709,
+        ColorMatrix::Bt2020 => SWS_CS_BT2020,
+    };
+    let src_range = (color.range == ColorRange::Full) as i32;
+    let dst_range = if rgb { 1 } else { src_range };
+
+    unsafe {
+        let coefficients = sws_getCoefficients(colorspace);
+        // brightness, contrast and saturation in 16.16 fixed point
+        sws_setColorspaceDetails(
+            scaler.as_mut_ptr(),
+            coefficients,
+            src_range,
+            coefficients,
+            dst_range,
+            0,
+            1 << 16,
+            1 << 16,
+        );
+    }
+}
+
+fn bit_depth(format: Pixel) -> u8 {
+    format
+        .descriptor()
+        .map_or(8, |desc| unsafe { (*desc.as_ptr()).comp[0].depth as u8 })
+}
+
+/// HDR metadata from the container (e.g. Matroska, MP4), if any.
+pub(crate) fn stream_hdr_metadata(stream: &Stream) -> Option<HdrMetadata> {
+    let mut hdr = None;
+    for side_data in stream.side_data() {
+        match side_data.kind() {
+            packet::side_data::Type::MasteringDisplayMetadata => {
+                parse_mastering_display(hdr.get_or_insert_default(), side_data.data())
+            }
+            packet::side_data::Type::ContentLightLevel => {
+                parse_content_light(hdr.get_or_insert_default(), side_data.data())
+            }
+            _ => {}
+        }
+    }
+    hdr
+}
+
+/// HDR metadata from SEI / OBU side data on a decoded frame, if any.
+fn frame_hdr_metadata(frame: &Video) -> Option<HdrMetadata> {
+    let mastering = frame.side_data(frame::side_data::Type::MasteringDisplayMetadata);
+    let light = frame.side_data(frame::side_data::Type::ContentLightLevel);
+    if mastering.is_none() && light.is_none() {
+        return None;
+    }
+
+    let mut hdr = HdrMetadata::default();
+    if let Some(side_data) = mastering {
+        parse_mastering_display(&mut hdr, side_data.data());
+    }
+    if let Some(side_data) = light {
+        parse_content_light(&mut hdr, side_data.data());
+    }
+    Some(hdr)
+}
+
+/// Reads an `AVMasteringDisplayMetadata`: ten `AVRational`s (primaries, white
+/// point, min and max luminance) followed by `has_primaries` and `has_luminance`.
+fn parse_mastering_display(hdr: &mut HdrMetadata, data: &[u8]) {
+    let int = |i: usize| {
+        data.get(i * 4..i * 4 + 4)
+            .map(|b| i32::from_ne_bytes([b[0], b[1], b[2], b[3]]))
+    };
+    let rational = |i: usize| match (int(2 * i), int(2 * i + 1)) {
+        (Some(num), Some(den)) if den != 0 => Some(num as f32 / den as f32),
+        _ => None,
+    };
+
+    if int(21).unwrap_or(0) != 0 {
+        hdr.min_luminance = rational(8);
+        hdr.max_luminance = rational(9);
+    }
+}
+
+/// Reads an `AVContentLightMetadata`: `MaxCLL` and `MaxFALL` as unsigned ints.
+fn parse_content_light(hdr: &mut HdrMetadata, data: &[u8]) {
+    if let Some(b) = data.get(0..4) {
+        let max_cll = u32::from_ne_bytes([b[0], b[1], b[2], b[3]]);
+        hdr.max_content_light = (max_cll > 0).then_some(max_cll as f32);
+    }
+}
+
+fn color_info(frame: &Video) -> ColorInfo {
+    let matrix = match frame.color_space() {
+        Space::BT709 => ColorMatrix::Bt709,
+        Space::BT2020NCL | Space::BT2020CL => ColorMatrix::Bt2020,
+        Space::BT470BG | Space::SMPTE170M | Space::SMPTE240M | Space::FCC => ColorMatrix::Bt601,
+        // Untagged → guess from the resolution, like most players
+        _ if frame.height() >= 720 => ColorMatrix::Bt709,
+        _ => ColorMatrix::Bt601,
+    };
+
+    let range = match frame.color_range() {
+        Range::JPEG => ColorRange::Full,
+        Range::MPEG => ColorRange::Limited,
+        _ => match frame.format() {
+            Pixel::YUVJ420P | Pixel::YUVJ422P | Pixel::YUVJ444P => ColorRange::Full,
+            _ => ColorRange::Limited,
+        },
+    };
+
+    let primaries = match frame.color_primaries() {
+        Primaries::BT709 => ColorPrimaries::Bt709,
+        Primaries::BT2020 => ColorPrimaries::Bt2020,
+        Primaries::BT470BG | Primaries::SMPTE170M | Primaries::SMPTE240M => ColorPrimaries::Bt601,
+        _ => match matrix {
+            ColorMatrix::Bt601 => ColorPrimaries::Bt601,
+            ColorMatrix::Bt709 => ColorPrimaries::Bt709,
+            ColorMatrix::Bt2020 => ColorPrimaries::Bt2020,
+        },
+    };
+
+    let transfer = match frame.color_transfer_characteristic() {
+        TransferCharacteristic::Linear => TransferFunction::Linear,
+        TransferCharacteristic::SMPTE2084 => TransferFunction::Pq,
+        TransferCharacteristic::ARIB_STD_B67 => TransferFunction::Hlg,
+        _ => TransferFunction::Sdr,
+    };
+
+    ColorInfo {
+        matrix,
+        range,
+        primaries,
+        transfer,
+        hdr: None,
+    }
+}
+
+/// Producer-side copy into a pooled buffer; readers are zero-copy. Each plane
+/// is copied whole, row padding included, so this is one `memcpy` per plane.
+fn copy_planes(src: &Video, format: PixelFormat, pool: &FramePool) -> (FrameData, [usize; 3]) {
+    let planes = format.plane_sizes(src.width(), src.height());
+    let mut stride = [0; 3];
+    for (i, stride) in stride.iter_mut().enumerate().take(planes.len()) {
+        *stride = src.stride(i);
+    }
+
+    let len = planes
+        .iter()
+        .zip(stride)
+        .map(|(&(_, rows), stride)| stride * rows)
+        .sum();
+    let mut data = pool.alloc(len);
+    for (i, &(_, rows)) in planes.iter().enumerate() {
+        data.extend_from_slice(&src.data(i)[..stride[i] * rows]);
+    }
+    (data, stride)
+}
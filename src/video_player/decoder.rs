@@ -1,97 +1,304 @@
 use crate::video_player::{
-    frame_buffer::{Frame, FrameBuffer},
-    PixelFormat, PlaybackParams,
+    audio::AudioTrack,
+    clock::PlaybackClock,
+    convert::FrameConverter,
+    error::PlayerError,
+    events::{EventReporter, PlayerEvent},
+    frame_buffer::{Frame, FrameBuffer, FrameQueue},
+    input::WatchedInput,
+    probe::video_info,
+    LoopMode, PlaybackParams, Reconnect, SeekMode,
 };
 use ffmpeg::{
     codec::context::Context,
-    format,
+    decoder,
+    error::{EAGAIN, ECONNRESET, EIO, ETIMEDOUT},
+    ffi::{AV_NOPTS_VALUE, AV_TIME_BASE},
+    format::Pixel,
     media::Type,
-    software::scaling::{Context as Scaler, Flags},
-    util::{format::Pixel, frame::Video},
+    util::frame::Video,
+    Discard, Packet, Stream,
 };
 use ffmpeg_next as ffmpeg;
 use std::{
-    error::Error,
     sync::{
         atomic::{AtomicBool, Ordering},
         Arc,
     },
     time::{Duration, Instant},
 };
+use tokio::sync::{
+    broadcast::Sender,
+    mpsc::{error::TryRecvError, UnboundedReceiver},
+};
+
+/// Control messages sent from the `VideoPlayer` to the decode thread.
+pub enum Command {
+    Seek {
+        position_us: u64,
+        mode: SeekMode,
+    },
+    /// Show the next frame now, regardless of the clock.
+    StepForward,
+    /// Show the frame before the last one shown.
+    StepBackward,
+    SetLoop(LoopMode),
+}
+
+/// Which frames to show right after a seek.
+#[derive(Clone, Copy)]
+enum SeekTarget {
+    /// Everything from this timestamp on (frame-accurate seek).
+    From(u64),
+    /// Only the last frame before this timestamp (step backward).
+    Before(u64),
+}
+
+impl SeekTarget {
+    #[inline]
+    fn ts_us(self) -> u64 {
+        match self {
+            SeekTarget::From(ts_us) | SeekTarget::Before(ts_us) => ts_us,
+        }
+    }
+}
+
+/// Outcome of waiting for a frame's presentation time.
+enum Wait {
+    Due,
+    Command(Command),
+    Shutdown,
+}
+
+/// Longest single sleep while waiting for a frame to become due, so that
+/// shutdown and commands are still noticed promptly.
+const MAX_WAIT_SLICE: Duration = Duration::from_millis(10);
+
+/// Decoded timestamps that go backwards, or jump ahead by more than this,
+/// re-anchor the clock instead of stalling.
+const MAX_CLOCK_DRIFT_US: u64 = 5_000_000;
+
+/// Above 1x, falling this far behind the clock starts dropping non-reference frames.
+const LATE_THRESHOLD_US: u64 = 100_000;
 
 pub fn loop_decoder(
     input: String,
     params: PlaybackParams,
     buffer: FrameBuffer,
+    clock: PlaybackClock,
+    mut commands: UnboundedReceiver<Command>,
+    events: Sender<PlayerEvent>,
     shutdown: Arc<AtomicBool>,
-) -> Result<(), Box<dyn Error + Send + Sync>> {
-    ffmpeg::init()?;
+) -> Result<(), PlayerError> {
+    ffmpeg::init().map_err(|e| PlayerError::open(&input, e))?;
+
+    let mut ictx = WatchedInput::open(&input, shutdown.clone(), params.read_timeout)?;
+    let mut reporter = EventReporter::new(events);
+    reporter.send(PlayerEvent::Opened(video_info(&ictx)?));
+    reporter.buffering();
 
-    let mut ictx = format::input(&input)?;
     let vstream = ictx
         .streams()
         .best(Type::Video)
-        .ok_or_else(|| "No video stream found")?;
-    let vindex = vstream.index();
-
-    let ctx = Context::from_parameters(vstream.parameters())?;
-    let mut dec = ctx.decoder().video()?;
+        .ok_or(PlayerError::NoVideoStream)?;
+    let mut vindex = vstream.index();
 
-    let out_w = dec.width();
-    let out_h = dec.height();
+    let mut dec = open_video_decoder(&vstream)?;
+    report_format_change(dec.width(), dec.height(), dec.format(), &mut reporter);
 
-    let out_pix = match params.pixel_format {
-        PixelFormat::RGB24 => Pixel::RGB24,
-        PixelFormat::RGBA => Pixel::RGBA,
-    };
+    let mut converter = FrameConverter::new(&vstream, &dec, &params, buffer.pool().clone())?;
 
-    // Build scaler.
-    let mut scaler = Scaler::get(
-        dec.format(),
-        dec.width(),
-        dec.height(),
-        out_pix,
-        out_w,
-        out_h,
-        Flags::BILINEAR,
-    )?;
+    // Audio (if any) becomes the master clock.
+    let mut audio = AudioTrack::open(&ictx, params.audio)?;
 
     let mut yuv = Video::empty();
-    let mut out = Video::empty();
 
     // PTS conversion + pacing info
-    let tb = vstream.time_base();
-    let fr = vstream.avg_frame_rate();
-    let frame_dt = if !params.is_live && fr.1 > 0 {
-        Duration::from_secs_f64(fr.1 as f64 / fr.0 as f64)
-    } else {
-        Duration::from_millis(0)
+    let mut tb = vstream.time_base();
+    let pace = !params.is_live;
+
+    // Looping → each pass is shifted by `loop_offset_us`, so `Frame::ts_us` and
+    // the clock keep increasing. Seek positions are on this shifted timeline.
+    let mut loop_mode = params.loop_mode;
+    let mut loop_offset_us: u64 = 0;
+    let stream_start_us = match vstream.start_time() {
+        AV_NOPTS_VALUE => 0,
+        pts => pts_to_us(pts, tb.0 as u32, tb.1 as u32).unwrap_or(0).max(0) as u64,
     };
-    let mut last_tick = Instant::now();
+    let frame_duration_us = frame_duration_us(vstream.avg_frame_rate());
+    // Set on start and after every seek: the next frame re-anchors the clock.
+    let mut anchor_clock = true;
+    let mut dropping_nonref = false;
+
+    let mut seek_target: Option<SeekTarget> = None;
+    // Last frame before a `SeekTarget::Before` seen so far.
+    let mut held_frame: Option<Arc<Frame>> = None;
+    let mut shown_ts_us: Option<u64> = None;
+    let mut step_next = false;
+    // Timestamp of the last frame decoded since a seek, to spot discontinuities.
+    let mut last_ts_us: Option<u64> = None;
+    // End of stream was reported and nothing was sought since.
+    let mut ended = false;
+    // A command that interrupted a wait, handled before anything new.
+    let mut pending_command = (params.start_us > 0).then_some(Command::Seek {
+        position_us: params.start_us,
+        mode: SeekMode::Accurate,
+    });
 
     // Main demux/decode
     let mut pkt_ctr = 0usize;
-    for (stream, packet) in ictx.packets() {
+    loop {
         if shutdown.load(Ordering::Relaxed) {
             buffer.finish();
             return Ok(());
         }
 
+        while let Some(command) = pending_command.take().or_else(|| commands.try_recv().ok()) {
+            let (position_us, target) = match command {
+                Command::Seek { position_us, mode } => {
+                    reporter.seek_started();
+                    match mode {
+                        SeekMode::Keyframe => (position_us, None),
+                        SeekMode::Accurate => (position_us, Some(SeekTarget::From(position_us))),
+                    }
+                }
+                Command::StepForward => {
+                    // A frame queued ahead is the next one; otherwise the next one decoded
+                    match buffer.next_ts_after(clock.now_us()) {
+                        Some(next_us) => clock.set(next_us),
+                        None => step_next = true,
+                    }
+                    continue;
+                }
+                // Back from the frame on screen, which may be behind those queued ahead
+                Command::StepBackward => match on_screen_ts_us(&buffer, shown_ts_us) {
+                    Some(ts_us) if ts_us > 0 => (ts_us - 1, Some(SeekTarget::Before(ts_us))),
+                    _ => continue,
+                },
+                Command::SetLoop(mode) => {
+                    loop_mode = mode;
+                    continue;
+                }
+            };
+
+            let ts = us_to_av_time(position_us.saturating_sub(loop_offset_us));
+            ictx.arm();
+            if let Err(e) = ictx.seek(ts, ..=ts) {
+                eprintln!("seek error: {e}");
+                reporter.seek_failed(&clock);
+                continue;
+            }
+
+            // Drop everything decoded before the seek point.
+            ended = false;
+            last_ts_us = None;
+            dec.flush();
+            buffer.flush();
+            anchor_clock = true;
+            seek_target = target;
+            held_frame = None;
+            if let Some(audio) = audio.as_mut() {
+                audio.flush(target.map(SeekTarget::ts_us), loop_offset_us);
+            }
+        }
+
+        if let Some(audio) = audio.as_mut() {
+            audio.sync_clock(&clock);
+        }
+        reporter.check_stall(&clock);
+
         // tiny cooperative yield to avoid hogging
         pkt_ctr += 1;
         if pkt_ctr % 5 == 0 {
             std::thread::sleep(Duration::from_millis(1));
         }
 
-        if stream.index() != vindex {
-            continue;
-        }
+        let looping = pace && loop_start_us(loop_mode, stream_start_us).is_some();
+        let mut packet = Packet::empty();
+        ictx.arm();
+        let at_end = match packet.read(&mut ictx) {
+            Ok(()) => false,
+            // Looping → drain the decoder below, then start over
+            Err(ffmpeg::Error::Eof) if looping => true,
+            Err(ffmpeg::Error::Other { errno: EAGAIN }) => continue,
+            // Interrupted for shutdown → noticed at the top of the loop
+            Err(ffmpeg::Error::Exit) if shutdown.load(Ordering::Relaxed) => continue,
+            // Live input has no end: running out of data means the connection dropped
+            Err(e)
+                if params.is_live
+                    && params.reconnect != Reconnect::Never
+                    && connection_lost(e, &ictx) =>
+            {
+                let cause = match ictx.timed_out() {
+                    true => PlayerError::Timeout,
+                    false => PlayerError::InputLost(e),
+                };
+                eprintln!("Input lost: {cause}");
+                let Some(reopened) = reconnect(
+                    &input,
+                    params.reconnect,
+                    params.read_timeout,
+                    cause,
+                    &shutdown,
+                    &mut reporter,
+                )?
+                else {
+                    continue;
+                };
 
-        // Be a bit resilient to transient send errors
-        if let Err(e) = dec.send_packet(&packet) {
-            eprintln!("decoder send_packet error: {e}");
-            std::thread::sleep(Duration::from_millis(2));
-            continue;
+                ictx = reopened;
+                let vstream = ictx
+                    .streams()
+                    .best(Type::Video)
+                    .ok_or(PlayerError::NoVideoStream)?;
+                vindex = vstream.index();
+                tb = vstream.time_base();
+                dec = open_video_decoder(&vstream)?;
+                dropping_nonref = false;
+
+                let audio_lost = match audio.as_mut().map(|audio| audio.reopen(&ictx)) {
+                    Some(Ok(found)) => !found,
+                    Some(Err(e)) => {
+                        eprintln!("Audio lost on reconnect: {e}");
+                        true
+                    }
+                    None => false,
+                };
+                if audio_lost {
+                    audio = None;
+                }
+
+                // Resume at whatever the live edge is now
+                anchor_clock = true;
+                last_ts_us = None;
+                seek_target = None;
+                held_frame = None;
+                reporter.send(PlayerEvent::Reconnected);
+                continue;
+            }
+            Err(ffmpeg::Error::Eof) => true,
+            Err(ffmpeg::Error::Exit) => return Err(PlayerError::Timeout),
+            Err(_) => continue,
+        };
+
+        if at_end {
+            let _ = dec.send_eof();
+        } else {
+            if let Some(audio) = audio.as_mut().filter(|a| a.index() == packet.stream()) {
+                audio.decode(&packet);
+                continue;
+            }
+
+            if packet.stream() != vindex {
+                continue;
+            }
+
+            // Be a bit resilient to transient send errors
+            if let Err(e) = dec.send_packet(&packet) {
+                eprintln!("decoder send_packet error: {e}");
+                std::thread::sleep(Duration::from_millis(2));
+                continue;
+            }
         }
 
         // Drain frames produced by this packet
@@ -100,84 +307,349 @@ pub fn loop_decoder(
                 buffer.finish();
                 return Ok(());
             }
+            report_format_change(yuv.width(), yuv.height(), yuv.format(), &mut reporter);
 
-            if let Err(e) = scaler.run(&yuv, &mut out) {
-                eprintln!("Scaling error: {e}");
-                continue;
+            let stream_ts_us = pts_to_us(yuv.timestamp().unwrap_or(0), tb.0 as u32, tb.1 as u32)
+                .unwrap_or(0)
+                .max(0) as u64;
+            let ts_us = stream_ts_us + loop_offset_us;
+
+            // Past the end of the loop range → start the next pass where this frame would be
+            if let LoopMode::Range(a_us, b_us) = loop_mode {
+                if pace && a_us < b_us && stream_ts_us >= b_us {
+                    pending_command = Some(loop_back(loop_mode, a_us, ts_us, &mut loop_offset_us));
+                    break;
+                }
+            }
+
+            match seek_target {
+                Some(SeekTarget::From(target)) if ts_us < target => continue,
+                Some(SeekTarget::Before(target)) if ts_us < target => {
+                    if let Ok(frame) = converter.convert(&yuv, ts_us, stream_ts_us) {
+                        held_frame = Some(frame);
+                    }
+                    continue;
+                }
+                Some(SeekTarget::Before(_)) => {
+                    // Reached the frame we stepped back from → show its predecessor and
+                    // leave this one pending behind the (paused) clock.
+                    if let Some(frame) = held_frame.take() {
+                        clock.set(frame.ts_us);
+                        shown_ts_us = Some(frame.ts_us);
+                        buffer.push(frame);
+                        reporter.frame_shown(&clock);
+                        anchor_clock = false;
+                    }
+                    seek_target = None;
+                }
+                Some(SeekTarget::From(_)) => seek_target = None,
+                None => {}
             }
 
-            // Producer-side copy → Arc<[u8]>; readers are zero-copy
-            let plane = out.data(0);
-            let pixels: Arc<[u8]> = Vec::from(plane).into();
+            // Sped up and can't keep up → let the decoder skip non-reference frames
+            // until we are back on time.
+            if pace {
+                let late_us = clock.now_us().saturating_sub(ts_us);
+                let drop_nonref = clock.rate() > 1.0
+                    && (late_us > LATE_THRESHOLD_US || (dropping_nonref && late_us > 0));
+                if drop_nonref != dropping_nonref {
+                    let discard = match drop_nonref {
+                        true => Discard::NonReference,
+                        false => Discard::Default,
+                    };
+                    set_skip_frame(&mut dec, discard);
+                    dropping_nonref = drop_nonref;
+                }
+            }
 
-            let ts_us =
-                pts_to_us(yuv.timestamp().unwrap_or(0), tb.0 as u32, tb.1 as u32).unwrap_or(0);
+            let frame = match converter.convert(&yuv, ts_us, stream_ts_us) {
+                Ok(frame) => frame,
+                Err(e) => {
+                    eprintln!("Scaling error: {e}");
+                    continue;
+                }
+            };
 
-            let frame = Arc::new(Frame {
-                data: pixels,
-                width: out_w as u32,
-                height: out_h as u32,
-                ts_us: ts_us as u64,
-            });
+            // Both are one-shot: take each, so neither outlives this frame
+            let anchor = std::mem::take(&mut anchor_clock);
+            let step = std::mem::take(&mut step_next);
+            // Timestamp discontinuity → follow the stream rather than stall or race
+            let jumped = pace
+                && last_ts_us
+                    .is_some_and(|last_us| ts_us < last_us || ts_us - last_us > MAX_CLOCK_DRIFT_US);
+            last_ts_us = Some(ts_us);
+            if anchor || step || jumped {
+                clock.set(ts_us);
+            }
+            match wait_until_due(
+                &buffer,
+                &clock,
+                &mut audio,
+                ts_us,
+                pace,
+                &mut commands,
+                &shutdown,
+                &mut loop_mode,
+            ) {
+                Wait::Due => {}
+                Wait::Command(Command::StepForward) => clock.set(ts_us),
+                Wait::Command(command) => {
+                    // The outer loop handles it; this frame is dropped.
+                    pending_command = Some(command);
+                    break;
+                }
+                Wait::Shutdown => break,
+            }
 
+            shown_ts_us = Some(ts_us);
             buffer.push(frame);
+            reporter.frame_shown(&clock);
+        }
 
-            // Pacing (only for VOD tests)
-            if frame_dt.as_millis() > 0 {
-                let elapsed = last_tick.elapsed();
-                let sleep = if elapsed < frame_dt {
-                    frame_dt - elapsed
-                } else {
-                    Duration::from_millis(1)
-                };
-                std::thread::sleep(sleep);
-                last_tick = Instant::now();
+        if at_end {
+            if let Some(audio) = audio.as_mut() {
+                audio.finish(&clock, || {
+                    shutdown.load(Ordering::Relaxed) || !commands.is_empty()
+                });
+            }
+            // A seek or step got in first
+            if pending_command.is_some() {
+                continue;
+            }
+            // Still looping → start the next pass
+            if let Some(start_us) = loop_start_us(loop_mode, stream_start_us).filter(|_| pace) {
+                let next_us = shown_ts_us
+                    .map_or(start_us + loop_offset_us, |ts_us| ts_us + frame_duration_us);
+                pending_command =
+                    Some(loop_back(loop_mode, start_us, next_us, &mut loop_offset_us));
+                continue;
+            }
+
+            // Stepped back onto the very last frame.
+            if let Some(frame) = held_frame.take() {
+                clock.set(frame.ts_us);
+                buffer.push(frame);
+                reporter.frame_shown(&clock);
+            }
+            // Let the frames a bounded queue holds ahead of the clock play out too
+            if buffer.queue() != FrameQueue::Latest {
+                while !buffer.is_empty() && !shutdown.load(Ordering::Relaxed) && commands.is_empty()
+                {
+                    std::thread::sleep(MAX_WAIT_SLICE);
+                }
+            }
+            if !ended && !shutdown.load(Ordering::Relaxed) && commands.is_empty() {
+                reporter.send(PlayerEvent::EndOfStream);
+                buffer.finish();
+                ended = true;
+            }
+
+            // Stay open for a seek or step back until stopped
+            match wait_for_command(&mut commands, &shutdown) {
+                Some(command) => pending_command = Some(command),
+                None => {
+                    buffer.finish();
+                    return Ok(());
+                }
             }
         }
     }
+}
+
+/// Read errors that mean a live input went away, as opposed to e.g. a corrupt packet.
+fn connection_lost(error: ffmpeg::Error, ictx: &WatchedInput) -> bool {
+    match error {
+        ffmpeg::Error::Eof => true,
+        ffmpeg::Error::Exit => ictx.timed_out(),
+        ffmpeg::Error::Other { errno } => matches!(errno, EIO | ECONNRESET | ETIMEDOUT),
+        _ => false,
+    }
+}
+
+/// The frame last taken out of `buffer`, or pushed into it (`shown_ts_us`)
+/// if that one is older, e.g. not taken yet after stepping back.
+fn on_screen_ts_us(buffer: &FrameBuffer, shown_ts_us: Option<u64>) -> Option<u64> {
+    match (buffer.presented_ts_us(), shown_ts_us) {
+        (Some(presented_us), Some(shown_us)) => Some(presented_us.min(shown_us)),
+        (presented_us, shown_us) => presented_us.or(shown_us),
+    }
+}
 
-    // Flush (files). Live may not reach here.
-    let _ = dec.send_eof();
-    while dec.receive_frame(&mut yuv).is_ok() {
+/// Blocks until the frame at `ts_us` is due on the playback clock.
+///
+/// Without pacing (live) frames are due as soon as the clock is running.
+/// A bounded `buffer` is paced by its consumer (`consume_due`) instead, so
+/// frames are due as soon as it has room. Stops early if a command or
+/// shutdown arrives while waiting, except for loop mode changes, which are
+/// applied to `loop_mode` in place, and steps onto a frame already queued.
+#[allow(clippy::too_many_arguments)]
+fn wait_until_due(
+    buffer: &FrameBuffer,
+    clock: &PlaybackClock,
+    audio: &mut Option<AudioTrack>,
+    ts_us: u64,
+    pace: bool,
+    commands: &mut UnboundedReceiver<Command>,
+    shutdown: &AtomicBool,
+    loop_mode: &mut LoopMode,
+) -> Wait {
+    loop {
         if shutdown.load(Ordering::Relaxed) {
-            buffer.finish();
-            return Ok(());
+            return Wait::Shutdown;
+        }
+        match commands.try_recv() {
+            Ok(Command::SetLoop(mode)) => *loop_mode = mode,
+            Ok(Command::StepForward) => match buffer.next_ts_after(clock.now_us()) {
+                Some(next_us) => clock.set(next_us),
+                None => return Wait::Command(Command::StepForward),
+            },
+            Ok(command) => return Wait::Command(command),
+            Err(_) => {}
         }
-        if scaler.run(&yuv, &mut out).is_ok() {
-            let plane = out.data(0);
-            let pixels: Arc<[u8]> = Vec::from(plane).into();
-            let ts_us =
-                pts_to_us(yuv.timestamp().unwrap_or(0), tb.0 as u32, tb.1 as u32).unwrap_or(0);
-            let frame = Arc::new(Frame {
-                data: pixels,
-                width: out_w as u32,
-                height: out_h as u32,
-                ts_us: ts_us as u64,
-            });
-            buffer.push(frame);
 
-            if frame_dt.as_millis() > 0 {
-                let elapsed = last_tick.elapsed();
-                let sleep = if elapsed < frame_dt {
-                    frame_dt - elapsed
-                } else {
-                    Duration::from_millis(1)
-                };
-                std::thread::sleep(sleep);
-                last_tick = Instant::now();
+        if let Some(audio) = audio.as_mut() {
+            audio.sync_clock(clock);
+        }
+
+        // Backpressure → wait for the consumer to take a frame
+        if !buffer.wait_for_space(MAX_WAIT_SLICE) {
+            continue;
+        }
+
+        let now_us = clock.now_us();
+        let paused = clock.is_paused();
+        if pace {
+            // Frames at or before a paused position are still due, e.g. right after a seek.
+            // A bounded queue fills ahead of the clock.
+            if ts_us <= now_us || buffer.queue() != FrameQueue::Latest {
+                return Wait::Due;
             }
+        } else if !paused {
+            return Wait::Due;
         }
+
+        let wait = if paused {
+            MAX_WAIT_SLICE
+        } else {
+            Duration::from_micros(((ts_us - now_us) as f64 / clock.rate()) as u64)
+        };
+        std::thread::sleep(wait.min(MAX_WAIT_SLICE));
     }
+}
 
-    buffer.finish();
-    Ok(())
+/// Block until a command arrives. `None` on shutdown or once the player is gone.
+fn wait_for_command(
+    commands: &mut UnboundedReceiver<Command>,
+    shutdown: &AtomicBool,
+) -> Option<Command> {
+    while !shutdown.load(Ordering::Relaxed) {
+        match commands.try_recv() {
+            Ok(command) => return Some(command),
+            Err(TryRecvError::Empty) => std::thread::sleep(MAX_WAIT_SLICE),
+            Err(TryRecvError::Disconnected) => break,
+        }
+    }
+    None
+}
+
+/// Where the next pass of a loop starts on the stream timeline, `None` when not looping.
+fn loop_start_us(mode: LoopMode, stream_start_us: u64) -> Option<u64> {
+    match mode {
+        LoopMode::Whole => Some(stream_start_us),
+        LoopMode::Range(a_us, b_us) if a_us < b_us => Some(a_us),
+        LoopMode::Off | LoopMode::Range(..) => None,
+    }
+}
+
+/// Shift the timeline so that `start_us` on the stream is shown at `next_us`,
+/// and return the seek back to it.
+fn loop_back(mode: LoopMode, start_us: u64, next_us: u64, loop_offset_us: &mut u64) -> Command {
+    *loop_offset_us = next_us.saturating_sub(start_us);
+    Command::Seek {
+        position_us: start_us + *loop_offset_us,
+        // A range starts exactly at A; the whole input from its first keyframe
+        mode: match mode {
+            LoopMode::Range(..) => SeekMode::Accurate,
+            _ => SeekMode::Keyframe,
+        },
+    }
+}
+
+/// Reopen lost live input, waiting between attempts as `policy` says. `None`
+/// on shutdown; `cause` (or the last failure) once `policy` gives up.
+fn reconnect(
+    input: &str,
+    policy: Reconnect,
+    read_timeout: Option<Duration>,
+    cause: PlayerError,
+    shutdown: &Arc<AtomicBool>,
+    reporter: &mut EventReporter,
+) -> Result<Option<WatchedInput>, PlayerError> {
+    let mut error = cause;
+    for attempt in 1..=u32::MAX {
+        let Some(delay) = policy.delay(attempt) else {
+            break;
+        };
+        reporter.reconnecting(attempt, delay);
+
+        let deadline = Instant::now() + delay;
+        while Instant::now() < deadline {
+            if shutdown.load(Ordering::Relaxed) {
+                return Ok(None);
+            }
+            std::thread::sleep(MAX_WAIT_SLICE);
+        }
+
+        match WatchedInput::open(input, shutdown.clone(), read_timeout) {
+            Ok(ictx) if ictx.streams().best(Type::Video).is_some() => return Ok(Some(ictx)),
+            Ok(_) => error = PlayerError::NoVideoStream,
+            Err(_) if shutdown.load(Ordering::Relaxed) => return Ok(None),
+            Err(e) => error = e,
+        }
+        eprintln!("Reconnect attempt {attempt} failed: {error}");
+    }
+    Err(error)
+}
+
+/// Announce a new size or pixel format of the decoded frames, once per change.
+fn report_format_change(width: u32, height: u32, format: Pixel, reporter: &mut EventReporter) {
+    let name = format.descriptor().map_or("unknown", |desc| desc.name());
+    reporter.video_format(width, height, name);
+}
+
+/// Length of one frame at `rate`, 40ms (25fps) when unknown.
+fn frame_duration_us(rate: ffmpeg::Rational) -> u64 {
+    match (rate.numerator(), rate.denominator()) {
+        (num, den) if num > 0 && den > 0 => den as u64 * 1_000_000 / num as u64,
+        _ => 40_000,
+    }
+}
+
+pub fn open_video_decoder(stream: &Stream) -> Result<decoder::Video, PlayerError> {
+    let parameters = stream.parameters();
+    let id = parameters.id();
+    Context::from_parameters(parameters)
+        .and_then(|ctx| ctx.decoder().video())
+        .map_err(|e| PlayerError::decoder(id, e))
 }
 
 #[inline]
-fn pts_to_us(pts: i64, tb_num: u32, tb_den: u32) -> Option<i64> {
+fn set_skip_frame(dec: &mut decoder::Video, discard: Discard) {
+    unsafe {
+        (*dec.as_mut_ptr()).skip_frame = discard.into();
+    }
+}
+
+#[inline]
+pub fn pts_to_us(pts: i64, tb_num: u32, tb_den: u32) -> Option<i64> {
     if tb_den == 0 {
         return None;
     }
     let us = (pts as i128) * (tb_num as i128) * 1_000_000i128 / (tb_den as i128);
     Some(us as i64)
 }
+
+#[inline]
+fn us_to_av_time(us: u64) -> i64 {
+    (us as i128 * AV_TIME_BASE as i128 / 1_000_000i128) as i64
+}
@@ -0,0 +1,114 @@
+use crate::video_player::audio_sink::{AudioSink, AudioSpec};
+use cpal::{
+    traits::{DeviceTrait, HostTrait, StreamTrait},
+    FromSample, SampleFormat, SizedSample, StreamConfig,
+};
+use std::{
+    collections::VecDeque,
+    error::Error,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+/// Plays through the default output device.
+pub struct DeviceSink {
+    spec: AudioSpec,
+    queue: Arc<Mutex<VecDeque<f32>>>,
+    played: Arc<AtomicU64>,
+    paused: Arc<AtomicBool>,
+    _stream: cpal::Stream,
+}
+
+impl DeviceSink {
+    pub fn open() -> Result<Self, Box<dyn Error>> {
+        let device = cpal::default_host()
+            .default_output_device()
+            .ok_or("No audio output device")?;
+        let supported = device.default_output_config()?;
+        let config = supported.config();
+        let spec = AudioSpec {
+            sample_rate: config.sample_rate.0,
+            channels: config.channels,
+        };
+
+        let queue = Arc::new(Mutex::new(VecDeque::<f32>::new()));
+        let played = Arc::new(AtomicU64::new(0));
+        let paused = Arc::new(AtomicBool::new(false));
+
+        // Samples are queued as f32 and converted to whatever the device takes.
+        let shared = (queue.clone(), played.clone(), paused.clone());
+        let stream = match supported.sample_format() {
+            SampleFormat::F32 => build_stream::<f32>(&device, &config, shared)?,
+            SampleFormat::F64 => build_stream::<f64>(&device, &config, shared)?,
+            SampleFormat::I16 => build_stream::<i16>(&device, &config, shared)?,
+            SampleFormat::I32 => build_stream::<i32>(&device, &config, shared)?,
+            SampleFormat::U8 => build_stream::<u8>(&device, &config, shared)?,
+            SampleFormat::U16 => build_stream::<u16>(&device, &config, shared)?,
+            format => return Err(format!("Unsupported sample format {format}").into()),
+        };
+        stream.play()?;
+
+        Ok(Self {
+            spec,
+            queue,
+            played,
+            paused,
+            _stream: stream,
+        })
+    }
+}
+
+type Shared = (Arc<Mutex<VecDeque<f32>>>, Arc<AtomicU64>, Arc<AtomicBool>);
+
+fn build_stream<T>(
+    device: &cpal::Device,
+    config: &StreamConfig,
+    (queue, played, paused): Shared,
+) -> Result<cpal::Stream, cpal::BuildStreamError>
+where
+    T: SizedSample + FromSample<f32>,
+{
+    let channels = config.channels as usize;
+    device.build_output_stream(
+        config,
+        move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
+            let mut filled = 0;
+            if !paused.load(Ordering::Relaxed) {
+                let mut queue = queue.lock().unwrap();
+                filled = data.len().min(queue.len());
+                for (dst, src) in data.iter_mut().zip(queue.drain(..filled)) {
+                    *dst = T::from_sample(src);
+                }
+            }
+            // Underrun or paused → silence
+            data[filled..].fill(T::EQUILIBRIUM);
+            played.fetch_add((filled / channels) as u64, Ordering::Relaxed);
+        },
+        |e| eprintln!("Audio stream error: {e}"),
+        None,
+    )
+}
+
+impl AudioSink for DeviceSink {
+    fn spec(&self) -> AudioSpec {
+        self.spec
+    }
+
+    fn write(&mut self, samples: &[f32]) {
+        self.queue.lock().unwrap().extend(samples);
+    }
+
+    fn played_frames(&self) -> u64 {
+        self.played.load(Ordering::Relaxed)
+    }
+
+    fn set_paused(&mut self, paused: bool) {
+        self.paused.store(paused, Ordering::Relaxed);
+    }
+
+    fn clear(&mut self) {
+        self.queue.lock().unwrap().clear();
+    }
+}
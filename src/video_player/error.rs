@@ -0,0 +1,79 @@
+use ffmpeg::{codec::Id, error::ETIMEDOUT};
+use ffmpeg_next as ffmpeg;
+use std::{error::Error, fmt};
+
+/// Errors reported by `VideoPlayer`, `get_video_info` and the decode thread.
+#[derive(Debug, Clone)]
+pub enum PlayerError {
+    /// The input could not be opened or its streams could not be read.
+    Open {
+        input: String,
+        source: ffmpeg::Error,
+    },
+    NoVideoStream,
+    /// No decoder for this codec is available in the linked FFmpeg.
+    UnsupportedCodec(Id),
+    Decoder(ffmpeg::Error),
+    Scaler(ffmpeg::Error),
+    /// The input stopped delivering data.
+    Timeout,
+    /// Live input failed or ended and could not be reopened.
+    InputLost(ffmpeg::Error),
+    /// Audio output could not be set up.
+    Audio(String),
+    /// A frame could not be written to an image file.
+    Snapshot(String),
+    AlreadyInitialized,
+    NotInitialized,
+    /// The decode thread has already exited.
+    NotRunning,
+}
+
+impl PlayerError {
+    pub(crate) fn open(input: &str, source: ffmpeg::Error) -> Self {
+        match source {
+            ffmpeg::Error::Other { errno: ETIMEDOUT } => PlayerError::Timeout,
+            source => PlayerError::Open {
+                input: input.to_string(),
+                source,
+            },
+        }
+    }
+
+    /// Error from opening a decoder for a stream with codec `id`.
+    pub(crate) fn decoder(id: Id, source: ffmpeg::Error) -> Self {
+        match source {
+            ffmpeg::Error::DecoderNotFound => PlayerError::UnsupportedCodec(id),
+            source => PlayerError::Decoder(source),
+        }
+    }
+}
+
+impl fmt::Display for PlayerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlayerError::Open { input, source } => write!(f, "Failed to open {input}: {source}"),
+            PlayerError::NoVideoStream => f.write_str("No video stream found"),
+            PlayerError::UnsupportedCodec(id) => write!(f, "Unsupported codec {id:?}"),
+            PlayerError::Decoder(e) => write!(f, "Decoder error: {e}"),
+            PlayerError::Scaler(e) => write!(f, "Scaler error: {e}"),
+            PlayerError::Timeout => f.write_str("Timed out reading the input"),
+            PlayerError::InputLost(e) => write!(f, "Lost the input: {e}"),
+            PlayerError::Audio(e) => write!(f, "Audio output error: {e}"),
+            PlayerError::Snapshot(e) => write!(f, "Failed to save frame: {e}"),
+            PlayerError::AlreadyInitialized => f.write_str("VideoPlayer is already initialized"),
+            PlayerError::NotInitialized => f.write_str("VideoPlayer is not initialized"),
+            PlayerError::NotRunning => f.write_str("Decoder is no longer running"),
+        }
+    }
+}
+
+impl Error for PlayerError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PlayerError::Open { source, .. } => Some(source),
+            PlayerError::Decoder(e) | PlayerError::Scaler(e) | PlayerError::InputLost(e) => Some(e),
+            _ => None,
+        }
+    }
+}
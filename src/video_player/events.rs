@@ -0,0 +1,188 @@
+use crate::video_player::{clock::PlaybackClock, error::PlayerError, probe::VideoInfo};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast::Sender;
+
+/// Going this long without a new frame while playing is reported as `Buffering`.
+const STALL_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// State changes reported through `VideoPlayer::events`.
+#[derive(Debug, Clone)]
+pub enum PlayerEvent {
+    /// The input was opened and has a video stream.
+    Opened(VideoInfo),
+    Playing,
+    Paused,
+    /// Waiting for frames: after opening, after a seek, or while the input stalls.
+    Buffering,
+    /// The first frame after a seek is available.
+    Seeked,
+    /// The decoded video changed size or pixel format mid-stream, e.g. on an HLS
+    /// variant switch. Source size, before `OutputSize`; frames already queued
+    /// keep the old size.
+    FormatChanged {
+        width: u32,
+        height: u32,
+        /// FFmpeg's name for the decoder's pixel format, e.g. `yuv420p10le`.
+        pixel_format: String,
+    },
+    /// Everything up to the end of the input was shown. The decoder stays
+    /// open, so seeking or stepping back resumes playback.
+    EndOfStream,
+    /// Live input was lost; attempt `attempt` (from 1) to reopen it starts after `delay`.
+    Reconnecting {
+        attempt: u32,
+        delay: Duration,
+    },
+    /// The input is open again; playback continues from its live edge.
+    Reconnected,
+    /// Playback stopped because of an error.
+    Error(PlayerError),
+}
+
+/// Decoder-side bookkeeping of what has been reported so far.
+pub struct EventReporter {
+    events: Sender<PlayerEvent>,
+    buffering: bool,
+    seeking: bool,
+    last_frame_at: Instant,
+    /// Size and pixel format of the decoded frames, as last reported.
+    video_format: Option<(u32, u32, &'static str)>,
+}
+
+impl EventReporter {
+    pub fn new(events: Sender<PlayerEvent>) -> Self {
+        Self {
+            events,
+            buffering: false,
+            seeking: false,
+            last_frame_at: Instant::now(),
+            video_format: None,
+        }
+    }
+
+    /// Send an event; having no subscribers is not an error.
+    #[inline]
+    pub fn send(&self, event: PlayerEvent) {
+        let _ = self.events.send(event);
+    }
+
+    /// Nothing can be shown until the next frame arrives.
+    pub fn buffering(&mut self) {
+        if !self.buffering {
+            self.buffering = true;
+            self.send(PlayerEvent::Buffering);
+        }
+    }
+
+    /// Live input was lost. Frames arriving once it is back report `Playing` again.
+    pub fn reconnecting(&mut self, attempt: u32, delay: Duration) {
+        self.buffering = true;
+        self.send(PlayerEvent::Reconnecting { attempt, delay });
+    }
+
+    pub fn seek_started(&mut self) {
+        self.seeking = true;
+        self.buffering();
+    }
+
+    /// Decoded frames are `width`x`height` in `pixel_format`. Sends `FormatChanged`
+    /// if that differs from the last call; the first call only records it.
+    pub fn video_format(&mut self, width: u32, height: u32, pixel_format: &'static str) {
+        let format = (width, height, pixel_format);
+        let last = self.video_format.replace(format);
+        if last.is_some_and(|last| last != format) {
+            self.send(PlayerEvent::FormatChanged {
+                width,
+                height,
+                pixel_format: pixel_format.to_string(),
+            });
+        }
+    }
+
+    /// A frame was pushed to the frame buffer.
+    pub fn frame_shown(&mut self, clock: &PlaybackClock) {
+        self.last_frame_at = Instant::now();
+
+        if std::mem::take(&mut self.seeking) {
+            self.send(PlayerEvent::Seeked);
+        }
+        self.resume(clock);
+    }
+
+    /// The seek could not be started; playback goes on from where it was.
+    pub fn seek_failed(&mut self, clock: &PlaybackClock) {
+        if std::mem::take(&mut self.seeking) {
+            self.resume(clock);
+        }
+    }
+
+    /// Leave `Buffering` for the state of the clock.
+    fn resume(&mut self, clock: &PlaybackClock) {
+        if std::mem::take(&mut self.buffering) {
+            self.send(match clock.is_paused() {
+                true => PlayerEvent::Paused,
+                false => PlayerEvent::Playing,
+            });
+        }
+    }
+
+    /// Report `Buffering` if frames stopped arriving while the clock is running.
+    pub fn check_stall(&mut self, clock: &PlaybackClock) {
+        if clock.is_paused() {
+            self.last_frame_at = Instant::now();
+        } else if self.last_frame_at.elapsed() > STALL_TIMEOUT {
+            self.buffering();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::broadcast;
+
+    #[test]
+    fn failed_seek_resumes_without_seeked() {
+        let (events, mut rx) = broadcast::channel(8);
+        let mut reporter = EventReporter::new(events);
+        let clock = PlaybackClock::new();
+
+        reporter.seek_started();
+        reporter.seek_failed(&clock);
+        reporter.frame_shown(&clock);
+        let sent: Vec<_> = std::iter::from_fn(|| rx.try_recv().ok()).collect();
+        assert!(
+            matches!(sent[..], [PlayerEvent::Buffering, PlayerEvent::Playing]),
+            "{sent:?}"
+        );
+    }
+
+    #[test]
+    fn format_change_is_reported_once() {
+        let (events, mut rx) = broadcast::channel(8);
+        let mut reporter = EventReporter::new(events);
+
+        reporter.video_format(1920, 1080, "yuv420p");
+        reporter.video_format(1920, 1080, "yuv420p");
+        assert!(rx.try_recv().is_err());
+
+        for _ in 0..3 {
+            reporter.video_format(1280, 720, "yuv420p");
+        }
+        reporter.video_format(1280, 720, "nv12");
+        let changes: Vec<_> = std::iter::from_fn(|| rx.try_recv().ok())
+            .map(|event| match event {
+                PlayerEvent::FormatChanged {
+                    width,
+                    height,
+                    pixel_format,
+                } => (width, height, pixel_format),
+                event => panic!("unexpected {event:?}"),
+            })
+            .collect();
+        assert_eq!(
+            changes,
+            [(1280, 720, "yuv420p".into()), (1280, 720, "nv12".into())]
+        );
+    }
+}
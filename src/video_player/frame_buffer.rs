@@ -1,59 +1,473 @@
-use std::sync::Arc;
-use tokio::sync::watch::{channel, Receiver, Sender};
+use crate::video_player::{
+    color::ColorInfo,
+    frame_pool::{FrameData, FramePool},
+    PixelFormat, Rotation,
+};
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+    time::Duration,
+};
+use tokio::sync::Notify;
 
 pub struct Frame {
     pub width: u32,
     pub height: u32,
-    pub data: Arc<[u8]>,
+    pub format: PixelFormat,
+    pub color: ColorInfo,
+    /// The planes of `format`, back to back. See `plane`.
+    pub data: FrameData,
+    /// Bytes from the start of one row of each plane to the next; at least the
+    /// visible row size, often more (decoder padding). 0 for unused planes.
+    pub stride: [usize; 3],
+    /// Turn to apply for display, from the stream's display matrix (phone footage).
+    pub rotation: Rotation,
+    /// Sample aspect ratio: the width of a pixel relative to its height. 1 for
+    /// square pixels; anamorphic content differs.
+    pub sar: f64,
+    /// Presentation time. Follows the stream, but keeps increasing when playback loops.
     pub ts_us: u64,
+    /// Timestamp on the stream's own timeline; differs from `ts_us` once playback has looped.
+    pub stream_ts_us: u64,
 }
 
+impl Frame {
+    /// Size as shown: pixels stretched to square ones, then `rotation` applied.
+    pub fn display_size(&self) -> (u32, u32) {
+        let width = match self.sar {
+            sar if sar.is_finite() && sar > 0.0 => (self.width as f64 * sar).round() as u32,
+            _ => self.width,
+        };
+        match self.rotation.is_transposed() {
+            true => (self.height, width.max(1)),
+            false => (width.max(1), self.height),
+        }
+    }
+
+    /// Rows of plane `index`, `stride[index]` bytes apart.
+    pub fn plane(&self, index: usize) -> Option<&[u8]> {
+        let mut offset = 0;
+        for (i, (_, rows)) in self
+            .format
+            .plane_sizes(self.width, self.height)
+            .into_iter()
+            .enumerate()
+        {
+            let len = self.stride.get(i)? * rows;
+            if i == index {
+                return self.data.get(offset..offset + len);
+            }
+            offset += len;
+        }
+        None
+    }
+}
+
+/// How decoded frames wait for the consumer.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FrameQueue {
+    /// A single slot. A frame nobody took yet is replaced by the next one.
+    #[default]
+    Latest,
+    /// Up to this many frames, oldest first. With pacing the decoder fills it
+    /// ahead of the clock for `VideoPlayer::next_due_frame`, and waits while it
+    /// is full, so no frame is lost to a slow consumer.
+    Bounded(usize),
+}
+
+impl FrameQueue {
+    /// Number of frames the buffer holds when full.
+    #[inline]
+    pub fn depth(self) -> usize {
+        match self {
+            FrameQueue::Latest => 1,
+            FrameQueue::Bounded(depth) => depth.max(1),
+        }
+    }
+}
+
+/// Frame counters of a `FrameBuffer`, see `VideoPlayer::stats`.
+/// Frames discarded by a seek are not counted as dropped.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct FrameStats {
+    /// Frames handed over by the decoder.
+    pub pushed: u64,
+    /// Frames taken out of the buffer to be shown.
+    pub presented: u64,
+    /// Frames skipped because a later one was taken: by `consume_due` when it
+    /// was due as well, or by `consume_latest`.
+    pub dropped_late: u64,
+    /// Frames replaced by a newer one before anyone took them.
+    pub dropped_overwritten: u64,
+    /// Frame allocations that reused a buffer from the pool.
+    pub pool_hits: u64,
+    /// Frame allocations that needed fresh memory.
+    pub pool_misses: u64,
+}
+
+/// Frames held outside the buffer at any one time: the one being uploaded,
+/// the one on screen and one spare, e.g. for a snapshot.
+pub const FRAMES_IN_FLIGHT: usize = 3;
+
 #[derive(Clone)]
 pub struct FrameBuffer {
-    tx: Sender<Option<Arc<Frame>>>,
-    rx: Receiver<Option<Arc<Frame>>>,
+    shared: Arc<Shared>,
+    pool: FramePool,
+}
+
+struct Shared {
+    queue: FrameQueue,
+    state: Mutex<State>,
+    /// Signalled when a frame is taken out, for a decoder waiting on a full queue.
+    space: Condvar,
+    /// Signalled when a frame arrives or pending frames are dropped, for `get_next`.
+    changed: Notify,
+}
+
+#[derive(Default)]
+struct State {
+    frames: VecDeque<Arc<Frame>>,
+    /// Frames were flushed, or the buffer finished, since `get_next` last returned.
+    interrupted: bool,
+    /// Timestamp of the last frame taken out, until a flush.
+    presented_ts_us: Option<u64>,
+    stats: FrameStats,
+}
+
+impl State {
+    fn take_front(&mut self) -> Option<Arc<Frame>> {
+        let frame = self.frames.pop_front()?;
+        self.stats.presented += 1;
+        self.presented_ts_us = Some(frame.ts_us);
+        Some(frame)
+    }
+}
+
+impl Default for FrameBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl FrameBuffer {
+    /// A single-slot buffer (`FrameQueue::Latest`) with a default-sized pool.
     pub fn new() -> Self {
-        let (tx, rx) = channel(None);
-        Self { tx, rx }
+        let queue = FrameQueue::Latest;
+        Self::with_queue(queue, queue.depth() + FRAMES_IN_FLIGHT)
+    }
+
+    /// `pool_size` idle frame buffers are kept for the decoder to reuse.
+    pub fn with_queue(queue: FrameQueue, pool_size: usize) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                queue,
+                state: Mutex::new(State::default()),
+                space: Condvar::new(),
+                changed: Notify::new(),
+            }),
+            pool: FramePool::new(pool_size),
+        }
+    }
+
+    /// Where the decoder gets storage for the frames it pushes.
+    #[inline]
+    pub fn pool(&self) -> &FramePool {
+        &self.pool
+    }
+
+    /// Number of frames the buffer holds when full.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.shared.queue.depth()
+    }
+
+    /// How frames wait for the consumer.
+    #[inline]
+    pub fn queue(&self) -> FrameQueue {
+        self.shared.queue
     }
 
-    /// Push a new frame into the buffer, overwrite existing frame if any.
+    /// Push a new frame into the buffer. When it is full the oldest pending
+    /// frame is overwritten; `wait_for_space` first to avoid that.
     pub fn push(&self, frame: Arc<Frame>) {
-        let _ = self.tx.send(Some(frame));
+        let mut state = self.lock();
+        state.stats.pushed += 1;
+        if state.frames.len() >= self.capacity() {
+            state.frames.pop_front();
+            state.stats.dropped_overwritten += 1;
+        }
+        state.frames.push_back(frame);
+        drop(state);
+        self.shared.changed.notify_one();
     }
 
-    /// Pull the latest frame from the buffer, if any. This does not consume the frame.
+    /// Block for up to `timeout` until a bounded queue has room for another
+    /// frame. Always true for `FrameQueue::Latest`, which overwrites instead.
+    pub fn wait_for_space(&self, timeout: Duration) -> bool {
+        if self.shared.queue == FrameQueue::Latest {
+            return true;
+        }
+
+        let capacity = self.capacity();
+        let (state, _) = self
+            .shared
+            .space
+            .wait_timeout_while(self.lock(), timeout, |state| state.frames.len() >= capacity)
+            .unwrap();
+        state.frames.len() < capacity
+    }
+
+    /// Pull the newest frame from the buffer, if any. This does not consume the frame.
     #[inline]
     pub fn pull(&self) -> Option<Arc<Frame>> {
-        self.rx.borrow().clone()
+        self.lock().frames.back().cloned()
     }
 
-    /// Consume the latest frame from the buffer.
-    #[inline]
+    /// Take the oldest frame from the buffer.
     pub fn consume(&mut self) -> Option<Arc<Frame>> {
-        self.rx.borrow_and_update().clone()
+        let frame = self.lock().take_front();
+        self.shared.space.notify_one();
+        frame
     }
 
-    /// Subscribe to latest frames.
+    /// Take the newest frame. Older pending frames are dropped as late.
+    pub fn consume_latest(&mut self) -> Option<Arc<Frame>> {
+        let mut state = self.lock();
+        let skipped = state.frames.len().saturating_sub(1);
+        state.frames.drain(..skipped);
+        state.stats.dropped_late += skipped as u64;
+        let frame = state.take_front();
+        drop(state);
+        self.shared.space.notify_one();
+        frame
+    }
+
+    /// Take the newest frame that is due at `now_us`. Older frames that are due
+    /// as well are dropped as late; frames after `now_us` stay in the buffer.
+    pub fn consume_due(&mut self, now_us: u64) -> Option<Arc<Frame>> {
+        let mut state = self.lock();
+        let due = state
+            .frames
+            .iter()
+            .take_while(|frame| frame.ts_us <= now_us)
+            .count();
+        if due == 0 {
+            return None;
+        }
+
+        state.frames.drain(..due - 1);
+        state.stats.dropped_late += due as u64 - 1;
+        let frame = state.take_front();
+        drop(state);
+        self.shared.space.notify_one();
+        frame
+    }
+
+    /// Timestamp of the first pending frame after `now_us`, i.e. the one
+    /// `consume_due` presents next.
+    pub fn next_ts_after(&self, now_us: u64) -> Option<u64> {
+        self.lock()
+            .frames
+            .iter()
+            .map(|frame| frame.ts_us)
+            .find(|&ts_us| ts_us > now_us)
+    }
+
+    /// Timestamp of the frame taken out last, `None` if none was since a flush.
+    pub fn presented_ts_us(&self) -> Option<u64> {
+        self.lock().presented_ts_us
+    }
+
+    /// Wait for the next frame. `None` when pending frames were dropped, e.g.
+    /// by a seek, or the buffer was finished.
     pub async fn get_next(&mut self) -> Option<Arc<Frame>> {
-        if self.rx.changed().await.is_ok() {
-            self.rx.borrow().clone()
-        } else {
-            None
+        loop {
+            {
+                let mut state = self.lock();
+                if let Some(frame) = state.take_front() {
+                    drop(state);
+                    self.shared.space.notify_one();
+                    return Some(frame);
+                }
+                if std::mem::take(&mut state.interrupted) {
+                    return None;
+                }
+            }
+            self.shared.changed.notified().await;
         }
     }
 
-    /// Finish the frame buffer, no more frames will be pushed.
+    /// Drop the pending frames, e.g. after a seek made them stale.
+    pub fn flush(&self) {
+        let mut state = self.lock();
+        state.frames.clear();
+        state.interrupted = true;
+        state.presented_ts_us = None;
+        drop(state);
+        self.shared.space.notify_one();
+        self.shared.changed.notify_one();
+    }
+
+    /// Finish the frame buffer, no more frames will be pushed. Frames already
+    /// in it can still be taken.
     pub fn finish(&self) {
-        let _ = self.tx.send(None);
+        self.lock().interrupted = true;
+        self.shared.changed.notify_one();
+    }
+
+    /// Number of frames waiting to be taken.
+    pub fn len(&self) -> usize {
+        self.lock().frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn stats(&self) -> FrameStats {
+        FrameStats {
+            pool_hits: self.pool.hits(),
+            pool_misses: self.pool.misses(),
+            ..self.lock().stats
+        }
     }
 
-    /// Subscribe to the frame buffer, get a receiver to receive latest frames.
     #[inline]
-    pub fn subscribe(&self) -> Receiver<Option<Arc<Frame>>> {
-        self.rx.clone()
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.shared.state.lock().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(ts_us: u64) -> Arc<Frame> {
+        Arc::new(Frame {
+            width: 2,
+            height: 2,
+            format: PixelFormat::GRAY8,
+            color: ColorInfo::default(),
+            data: vec![0; 4].into(),
+            stride: [2, 0, 0],
+            rotation: Rotation::None,
+            sar: 1.0,
+            ts_us,
+            stream_ts_us: ts_us,
+        })
+    }
+
+    #[test]
+    fn planes_follow_stride() {
+        const PADDING: usize = 3;
+        for format in [
+            PixelFormat::RGBA,
+            PixelFormat::RGB24,
+            PixelFormat::BGRA,
+            PixelFormat::GRAY8,
+            PixelFormat::Yuv420p,
+            PixelFormat::Nv12,
+            PixelFormat::P010,
+        ] {
+            let sizes = format.plane_sizes(5, 3);
+            let mut stride = [0; 3];
+            let mut data = Vec::new();
+            for (i, &(row, rows)) in sizes.iter().enumerate() {
+                stride[i] = row + PADDING;
+                data.extend(std::iter::repeat_n(i as u8, stride[i] * rows));
+            }
+            let frame = Frame {
+                width: 5,
+                height: 3,
+                format,
+                color: ColorInfo::default(),
+                data: data.into(),
+                stride,
+                rotation: Rotation::None,
+                sar: 1.0,
+                ts_us: 0,
+                stream_ts_us: 0,
+            };
+
+            for (i, &(_, rows)) in sizes.iter().enumerate() {
+                let plane = frame.plane(i).unwrap();
+                assert_eq!(plane.len(), stride[i] * rows, "{format:?} plane {i}");
+                assert!(plane.iter().all(|&b| b == i as u8), "{format:?} plane {i}");
+            }
+            assert!(frame.plane(sizes.len()).is_none(), "{format:?}");
+        }
+    }
+
+    fn queued(depth: usize, timestamps: &[u64]) -> FrameBuffer {
+        let buffer = FrameBuffer::with_queue(FrameQueue::Bounded(depth), depth);
+        for &ts_us in timestamps {
+            buffer.push(frame(ts_us));
+        }
+        buffer
+    }
+
+    #[test]
+    fn display_size_swaps_for_quarter_turns() {
+        let mut frame = Arc::into_inner(frame(0)).unwrap();
+        frame.width = 4;
+        for (rotation, size) in [
+            (Rotation::None, (4, 2)),
+            (Rotation::Cw90, (2, 4)),
+            (Rotation::Cw180, (4, 2)),
+            (Rotation::Cw270, (2, 4)),
+        ] {
+            frame.rotation = rotation;
+            assert_eq!(frame.display_size(), size, "{rotation:?}");
+        }
+
+        // Pixels are made square before turning
+        frame.sar = 2.0;
+        frame.rotation = Rotation::Cw270;
+        assert_eq!(frame.display_size(), (2, 8));
+    }
+
+    #[test]
+    fn latest_slot_overwrites() {
+        let mut buffer = FrameBuffer::new();
+        buffer.push(frame(0));
+        buffer.push(frame(40));
+        assert_eq!(buffer.consume().map(|f| f.ts_us), Some(40));
+        assert_eq!(buffer.stats().dropped_overwritten, 1);
+    }
+
+    #[test]
+    fn consume_due_takes_newest_due_frame() {
+        let mut buffer = queued(4, &[0, 40, 80, 120]);
+        assert_eq!(buffer.consume_due(90).map(|f| f.ts_us), Some(80));
+        assert_eq!(buffer.stats().dropped_late, 2);
+        assert!(buffer.consume_due(90).is_none());
+        assert_eq!(buffer.next_ts_after(90), Some(120));
+        assert_eq!(buffer.presented_ts_us(), Some(80));
+    }
+
+    #[test]
+    fn queued_and_latest_order() {
+        let mut buffer = queued(3, &[0, 40, 80]);
+        assert_eq!(buffer.consume().map(|f| f.ts_us), Some(0));
+        assert_eq!(buffer.consume_latest().map(|f| f.ts_us), Some(80));
+        assert_eq!(buffer.stats().dropped_late, 1);
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn bounded_queue_waits_for_space() {
+        let mut buffer = queued(2, &[0, 40]);
+        assert!(!buffer.wait_for_space(Duration::from_millis(1)));
+        buffer.consume();
+        assert!(buffer.wait_for_space(Duration::from_millis(1)));
+    }
+
+    #[test]
+    fn flush_forgets_presented_frame() {
+        let mut buffer = queued(2, &[0, 40]);
+        buffer.consume();
+        buffer.flush();
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.presented_ts_us(), None);
     }
 }
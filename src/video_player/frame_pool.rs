@@ -0,0 +1,179 @@
+use std::{
+    ops::Deref,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, Weak,
+    },
+};
+
+/// Recycles `Frame` storage: when the last reference to a frame is dropped its
+/// buffer goes back to the pool, and the decoder fills it again instead of
+/// allocating a new one.
+#[derive(Clone)]
+pub struct FramePool {
+    shared: Arc<PoolShared>,
+}
+
+struct PoolShared {
+    /// Idle buffers kept at most; more are freed.
+    size: usize,
+    free: Mutex<Vec<Vec<u8>>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+/// Frame bytes, handed back to their `FramePool` when dropped.
+pub struct FrameData {
+    bytes: Vec<u8>,
+    pool: Weak<PoolShared>,
+}
+
+impl FramePool {
+    /// Keep up to `size` idle buffers. `0` disables recycling.
+    pub fn new(size: usize) -> Self {
+        Self {
+            shared: Arc::new(PoolShared {
+                size,
+                free: Mutex::new(Vec::with_capacity(size)),
+                hits: AtomicU64::new(0),
+                misses: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    /// An empty buffer with room for `len` bytes, recycled if one is idle.
+    pub fn alloc(&self, len: usize) -> FrameData {
+        let recycled = self.shared.free.lock().unwrap().pop();
+        let bytes = match recycled {
+            // Too small after a resolution change → let it go
+            Some(bytes) if bytes.capacity() >= len => {
+                self.shared.hits.fetch_add(1, Ordering::Relaxed);
+                bytes
+            }
+            _ => {
+                self.shared.misses.fetch_add(1, Ordering::Relaxed);
+                Vec::with_capacity(len)
+            }
+        };
+
+        FrameData {
+            bytes,
+            pool: Arc::downgrade(&self.shared),
+        }
+    }
+
+    /// Allocations served from an idle buffer.
+    pub fn hits(&self) -> u64 {
+        self.shared.hits.load(Ordering::Relaxed)
+    }
+
+    /// Allocations that needed fresh memory.
+    pub fn misses(&self) -> u64 {
+        self.shared.misses.load(Ordering::Relaxed)
+    }
+}
+
+impl FrameData {
+    #[inline]
+    pub fn extend_from_slice(&mut self, bytes: &[u8]) {
+        self.bytes.extend_from_slice(bytes);
+    }
+}
+
+impl Deref for FrameData {
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+/// Bytes that don't belong to any pool.
+impl From<Vec<u8>> for FrameData {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self {
+            bytes,
+            pool: Weak::new(),
+        }
+    }
+}
+
+impl Drop for FrameData {
+    fn drop(&mut self) {
+        let Some(pool) = self.pool.upgrade() else {
+            return;
+        };
+
+        let mut free = pool.free.lock().unwrap();
+        if free.len() < pool.size {
+            let mut bytes = std::mem::take(&mut self.bytes);
+            bytes.clear();
+            free.push(bytes);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn idle(pool: &FramePool) -> usize {
+        pool.shared.free.lock().unwrap().len()
+    }
+
+    #[test]
+    fn dropped_buffer_is_reused() {
+        let pool = FramePool::new(2);
+        let mut data = pool.alloc(16);
+        data.extend_from_slice(&[1; 16]);
+        let ptr = data.as_ptr();
+        drop(data);
+        assert_eq!(idle(&pool), 1);
+
+        let data = pool.alloc(16);
+        assert_eq!(data.as_ptr(), ptr);
+        assert!(data.is_empty());
+        assert_eq!((pool.hits(), pool.misses()), (1, 1));
+    }
+
+    #[test]
+    fn too_small_buffer_is_discarded() {
+        let pool = FramePool::new(2);
+        drop(pool.alloc(16));
+
+        let data = pool.alloc(64);
+        assert!(data.bytes.capacity() >= 64);
+        assert_eq!((pool.hits(), pool.misses()), (0, 2));
+        // Taken out and not put back
+        assert_eq!(idle(&pool), 0);
+    }
+
+    #[test]
+    fn keeps_at_most_size_idle_buffers() {
+        let pool = FramePool::new(2);
+        let frames: Vec<_> = (0..3).map(|_| pool.alloc(16)).collect();
+        drop(frames);
+        assert_eq!(idle(&pool), 2);
+    }
+
+    #[test]
+    fn size_zero_disables_recycling() {
+        let pool = FramePool::new(0);
+        drop(pool.alloc(16));
+        assert_eq!(idle(&pool), 0);
+
+        drop(pool.alloc(16));
+        assert_eq!((pool.hits(), pool.misses()), (0, 2));
+    }
+
+    #[test]
+    fn vec_data_never_enters_a_pool() {
+        let pool = FramePool::new(2);
+        drop(FrameData::from(vec![0; 16]));
+        assert_eq!(idle(&pool), 0);
+
+        drop(pool.alloc(16));
+        assert_eq!((pool.hits(), pool.misses()), (0, 1));
+    }
+}
@@ -0,0 +1,194 @@
+use crate::video_player::error::PlayerError;
+use ffmpeg::{
+    ffi::{
+        avformat_alloc_context, avformat_close_input, avformat_find_stream_info,
+        avformat_open_input, AVIOInterruptCB,
+    },
+    format::context::Input,
+};
+use ffmpeg_next as ffmpeg;
+use std::{
+    ffi::{c_int, c_void, CString},
+    ops::{Deref, DerefMut},
+    ptr,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+/// An opened input whose blocking I/O gives up on shutdown, or when a single
+/// open or read takes longer than the read timeout.
+pub struct WatchedInput {
+    // Dropped first: FFmpeg may call into the watchdog until the input is closed
+    ictx: Input,
+    watchdog: Box<Watchdog>,
+}
+
+/// State behind FFmpeg's interrupt callback.
+struct Watchdog {
+    shutdown: Arc<AtomicBool>,
+    timeout: Option<Duration>,
+    epoch: Instant,
+    // Milliseconds since `epoch` at which the current blocking call started
+    armed_at_ms: AtomicU64,
+}
+
+impl Watchdog {
+    fn new(shutdown: Arc<AtomicBool>, timeout: Option<Duration>) -> Self {
+        Self {
+            shutdown,
+            timeout,
+            epoch: Instant::now(),
+            armed_at_ms: AtomicU64::new(0),
+        }
+    }
+
+    #[inline]
+    fn now_ms(&self) -> u64 {
+        self.epoch.elapsed().as_millis() as u64
+    }
+
+    /// Start timing a new blocking call.
+    fn arm(&self) {
+        self.armed_at_ms.store(self.now_ms(), Ordering::Relaxed);
+    }
+
+    fn timed_out(&self) -> bool {
+        self.timeout.is_some_and(|timeout| {
+            let armed_at_ms = self.armed_at_ms.load(Ordering::Relaxed);
+            self.now_ms().saturating_sub(armed_at_ms) > timeout.as_millis() as u64
+        })
+    }
+
+    /// Open errors, with an open cut short by the timeout reported as such.
+    fn error(&self, input: &str, source: ffmpeg::Error) -> PlayerError {
+        match source {
+            ffmpeg::Error::Exit if self.timed_out() => PlayerError::Timeout,
+            source => PlayerError::open(input, source),
+        }
+    }
+}
+
+/// Polled by FFmpeg while it blocks; non-zero aborts the call with `Error::Exit`.
+unsafe extern "C" fn interrupt(opaque: *mut c_void) -> c_int {
+    let watchdog = &*(opaque as *const Watchdog);
+    (watchdog.shutdown.load(Ordering::Relaxed) || watchdog.timed_out()) as c_int
+}
+
+impl WatchedInput {
+    /// Open `input` and read its stream info; both count as one blocking call.
+    pub fn open(
+        input: &str,
+        shutdown: Arc<AtomicBool>,
+        timeout: Option<Duration>,
+    ) -> Result<Self, PlayerError> {
+        let watchdog = Box::new(Watchdog::new(shutdown, timeout));
+        let path = CString::new(input)
+            .map_err(|_| PlayerError::open(input, ffmpeg::Error::InvalidData))?;
+
+        let ictx = unsafe {
+            let mut ps = avformat_alloc_context();
+            (*ps).interrupt_callback = AVIOInterruptCB {
+                callback: Some(interrupt),
+                opaque: &*watchdog as *const Watchdog as *mut c_void,
+            };
+
+            // Frees `ps` on failure
+            match avformat_open_input(&mut ps, path.as_ptr(), ptr::null_mut(), ptr::null_mut()) {
+                0 => match avformat_find_stream_info(ps, ptr::null_mut()) {
+                    r if r >= 0 => Input::wrap(ps),
+                    e => {
+                        avformat_close_input(&mut ps);
+                        return Err(watchdog.error(input, ffmpeg::Error::from(e)));
+                    }
+                },
+                e => return Err(watchdog.error(input, ffmpeg::Error::from(e))),
+            }
+        };
+
+        Ok(Self { ictx, watchdog })
+    }
+
+    /// Restart the read timeout; call before each blocking read or seek.
+    #[inline]
+    pub fn arm(&self) {
+        self.watchdog.arm();
+    }
+
+    /// The last blocking call was cut short by the read timeout.
+    pub fn timed_out(&self) -> bool {
+        !self.watchdog.shutdown.load(Ordering::Relaxed) && self.watchdog.timed_out()
+    }
+}
+
+impl Deref for WatchedInput {
+    type Target = Input;
+
+    #[inline]
+    fn deref(&self) -> &Input {
+        &self.ictx
+    }
+}
+
+impl DerefMut for WatchedInput {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Input {
+        &mut self.ictx
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn interrupted(watchdog: &Watchdog) -> bool {
+        unsafe { interrupt(watchdog as *const Watchdog as *mut c_void) != 0 }
+    }
+
+    #[test]
+    fn watchdog_times_out_until_rearmed() {
+        let watchdog = Watchdog::new(
+            Arc::new(AtomicBool::new(false)),
+            Some(Duration::from_millis(50)),
+        );
+        watchdog.arm();
+        assert!(!watchdog.timed_out());
+        assert!(!interrupted(&watchdog));
+
+        std::thread::sleep(Duration::from_millis(120));
+        assert!(watchdog.timed_out());
+        assert!(interrupted(&watchdog));
+
+        watchdog.arm();
+        assert!(!watchdog.timed_out());
+        assert!(!interrupted(&watchdog));
+    }
+
+    #[test]
+    fn watchdog_without_timeout_only_stops_on_shutdown() {
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let watchdog = Watchdog::new(shutdown.clone(), None);
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(!watchdog.timed_out());
+        assert!(!interrupted(&watchdog));
+
+        shutdown.store(true, Ordering::Relaxed);
+        assert!(interrupted(&watchdog));
+    }
+
+    #[test]
+    fn open_error_reports_timeout() {
+        let watchdog = Watchdog::new(Arc::new(AtomicBool::new(false)), Some(Duration::ZERO));
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(matches!(
+            watchdog.error("in", ffmpeg::Error::Exit),
+            PlayerError::Timeout
+        ));
+        assert!(matches!(
+            watchdog.error("in", ffmpeg::Error::InvalidData),
+            PlayerError::Open { .. }
+        ));
+    }
+}
@@ -0,0 +1,221 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+/// One demuxed video packet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct IndexEntry {
+    /// Presentation time on the stream timeline, the same as `Frame::stream_ts_us`.
+    pub pts_us: i64,
+    /// Offset of the packet in the input, when the demuxer knows it.
+    pub byte_pos: Option<u64>,
+    pub is_keyframe: bool,
+    /// Packet size in bytes.
+    pub size: u32,
+}
+
+/// Every packet of the best video stream, in demux order.
+/// Built by `build_keyframe_index`.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct KeyframeIndex {
+    pub entries: Vec<IndexEntry>,
+}
+
+/// First line of a sidecar file, followed by the input's fingerprint.
+const SIDECAR_HEADER: &str = "keyframe-index v1";
+
+impl KeyframeIndex {
+    pub fn keyframes(&self) -> impl Iterator<Item = &IndexEntry> {
+        self.entries.iter().filter(|entry| entry.is_keyframe)
+    }
+
+    /// Last keyframe at or before `ts_us`: where decoding has to start to show `ts_us`.
+    pub fn keyframe_before(&self, ts_us: i64) -> Option<&IndexEntry> {
+        self.keyframes()
+            .filter(|entry| entry.pts_us <= ts_us)
+            .max_by_key(|entry| entry.pts_us)
+    }
+
+    /// Where the index of `input` is cached: `<input>.keyframes`.
+    pub fn sidecar_path(input: &Path) -> PathBuf {
+        let mut path = input.as_os_str().to_owned();
+        path.push(".keyframes");
+        path.into()
+    }
+
+    /// Write the index as text, one `pts_us byte_pos is_keyframe size` line per
+    /// packet. The header records `input`'s size and modification time, so
+    /// `load` can tell when the input has changed since.
+    pub fn save(&self, path: &Path, input: &Path) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        writeln!(out, "{SIDECAR_HEADER} {}", fingerprint(input)?)?;
+        for entry in &self.entries {
+            let byte_pos = entry.byte_pos.map_or(-1, |pos| pos as i64);
+            let is_keyframe = entry.is_keyframe as u8;
+            writeln!(
+                out,
+                "{} {byte_pos} {is_keyframe} {}",
+                entry.pts_us, entry.size
+            )?;
+        }
+        out.flush()
+    }
+
+    /// Read an index written by `save`. `None` if there is none, or it is from
+    /// another version or an older copy of `input`.
+    pub fn load(path: &Path, input: &Path) -> io::Result<Option<Self>> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        let mut lines = BufReader::new(file).lines();
+        let header = format!("{SIDECAR_HEADER} {}", fingerprint(input)?);
+        if lines.next().transpose()?.as_deref() != Some(header.as_str()) {
+            return Ok(None);
+        }
+
+        let mut entries = Vec::new();
+        for line in lines {
+            let line = line?;
+            let entry = parse_entry(&line).ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData, format!("bad entry `{line}`"))
+            })?;
+            entries.push(entry);
+        }
+        Ok(Some(Self { entries }))
+    }
+}
+
+fn parse_entry(line: &str) -> Option<IndexEntry> {
+    let mut fields = line.split_whitespace();
+    let pts_us = fields.next()?.parse().ok()?;
+    let byte_pos = fields.next()?.parse::<i64>().ok()?;
+    let is_keyframe = fields.next()? == "1";
+    let size = fields.next()?.parse().ok()?;
+
+    Some(IndexEntry {
+        pts_us,
+        byte_pos: u64::try_from(byte_pos).ok(),
+        is_keyframe,
+        size,
+    })
+}
+
+/// Size and modification time; changes whenever the file is rewritten.
+fn fingerprint(input: &Path) -> io::Result<String> {
+    let metadata = fs::metadata(input)?;
+    let modified = metadata
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_nanos());
+    Ok(format!("{} {modified}", metadata.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A scratch input and its sidecar path, removed on drop.
+    struct TempInput(PathBuf);
+
+    impl TempInput {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("{name}-{}.ts", std::process::id()));
+            fs::write(&path, b"not really video").unwrap();
+            Self(path)
+        }
+
+        fn sidecar(&self) -> PathBuf {
+            KeyframeIndex::sidecar_path(&self.0)
+        }
+    }
+
+    impl Drop for TempInput {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(self.sidecar());
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    fn index() -> KeyframeIndex {
+        KeyframeIndex {
+            entries: vec![
+                IndexEntry {
+                    pts_us: -40_000,
+                    byte_pos: Some(0),
+                    is_keyframe: true,
+                    size: 1200,
+                },
+                IndexEntry {
+                    pts_us: 0,
+                    byte_pos: None,
+                    is_keyframe: false,
+                    size: 300,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let input = TempInput::new("keyframe-index-round-trip");
+        index().save(&input.sidecar(), &input.0).unwrap();
+
+        let loaded = KeyframeIndex::load(&input.sidecar(), &input.0).unwrap();
+        assert_eq!(loaded.unwrap().entries, index().entries);
+    }
+
+    #[test]
+    fn changed_input_is_not_loaded() {
+        let input = TempInput::new("keyframe-index-changed");
+        index().save(&input.sidecar(), &input.0).unwrap();
+        fs::write(&input.0, b"rewritten, and longer than before").unwrap();
+
+        let loaded = KeyframeIndex::load(&input.sidecar(), &input.0).unwrap();
+        assert!(loaded.is_none());
+    }
+
+    #[test]
+    fn missing_sidecar_is_not_an_error() {
+        let input = TempInput::new("keyframe-index-missing");
+        let loaded = KeyframeIndex::load(&input.sidecar(), &input.0).unwrap();
+        assert!(loaded.is_none());
+    }
+
+    #[test]
+    fn malformed_entry_is_rejected() {
+        let input = TempInput::new("keyframe-index-malformed");
+        let header = format!("{SIDECAR_HEADER} {}", fingerprint(&input.0).unwrap());
+        fs::write(
+            input.sidecar(),
+            format!("{header}\n0 0 1 100\n40000 x 0 50\n"),
+        )
+        .unwrap();
+
+        let err = KeyframeIndex::load(&input.sidecar(), &input.0).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn parse_entry_needs_every_field() {
+        assert_eq!(
+            parse_entry("40000 -1 0 50"),
+            Some(IndexEntry {
+                pts_us: 40_000,
+                byte_pos: None,
+                is_keyframe: false,
+                size: 50,
+            })
+        );
+        assert_eq!(parse_entry("40000 -1 0"), None);
+        assert_eq!(parse_entry("40000 -1 0 big"), None);
+        assert_eq!(parse_entry(""), None);
+    }
+}
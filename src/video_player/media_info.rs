@@ -0,0 +1,99 @@
+use crate::video_player::color::HdrMetadata;
+use std::collections::BTreeMap;
+
+/// Description of an input and every stream in it, from `get_media_info`.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct MediaInfo {
+    /// Demuxer name, e.g. `mov,mp4,m4a,3gp,3g2,mj2` or `hls`.
+    pub format_name: String,
+    pub format_long_name: String,
+    pub duration_us: Option<i64>,
+    /// Overall bitrate in bits per second.
+    pub bit_rate: Option<i64>,
+    pub metadata: BTreeMap<String, String>,
+    pub chapters: Vec<ChapterInfo>,
+    pub streams: Vec<StreamInfo>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ChapterInfo {
+    pub id: i64,
+    pub start_us: i64,
+    pub end_us: i64,
+    pub title: Option<String>,
+    pub metadata: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum StreamKind {
+    Video,
+    Audio,
+    Subtitle,
+    Data,
+    Attachment,
+    Unknown,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct StreamInfo {
+    pub index: usize,
+    pub kind: StreamKind,
+    /// Short codec name, e.g. `h264`.
+    pub codec: String,
+    pub codec_long_name: Option<String>,
+    pub profile: Option<String>,
+    /// Codec-specific level, e.g. 41 for H.264 level 4.1.
+    pub level: Option<i32>,
+    /// Bits per second, when the container states it.
+    pub bit_rate: Option<i64>,
+    pub duration_us: Option<i64>,
+    /// From the `language` tag, usually an ISO 639-2 code such as `eng`.
+    pub language: Option<String>,
+    /// Marked as the default stream of its kind.
+    pub is_default: bool,
+    pub metadata: BTreeMap<String, String>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub video: Option<VideoStreamInfo>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub audio: Option<AudioStreamInfo>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct VideoStreamInfo {
+    pub width: u32,
+    pub height: u32,
+    /// FFmpeg pixel format name, e.g. `yuv420p10le`.
+    pub pixel_format: Option<String>,
+    pub avg_fps: Option<f64>,
+    /// Shape of a single pixel as `(num, den)`; `None` when unknown.
+    pub sample_aspect_ratio: Option<(i32, i32)>,
+    /// Clockwise rotation, in degrees, to apply for display.
+    pub rotation: Option<f64>,
+    // FFmpeg's names for the color tags, e.g. `bt2020nc`, `tv`, `bt2020`, `smpte2084`
+    pub color_space: Option<String>,
+    pub color_range: Option<String>,
+    pub color_primaries: Option<String>,
+    pub color_transfer: Option<String>,
+    /// Mastering display and content light levels stored in the container.
+    pub hdr: Option<HdrMetadata>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct AudioStreamInfo {
+    pub sample_rate: u32,
+    pub channels: u32,
+    /// E.g. `stereo` or `5.1(side)`.
+    pub channel_layout: Option<String>,
+    /// FFmpeg sample format name, e.g. `fltp`.
+    pub sample_format: Option<String>,
+}
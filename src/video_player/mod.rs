@@ -1,43 +1,362 @@
 use std::{
-    error::Error,
+    path::{Path, PathBuf},
     sync::{
         atomic::{AtomicBool, Ordering},
         Arc,
     },
+    time::Duration,
 };
+use tokio::sync::{
+    broadcast,
+    mpsc::{unbounded_channel, UnboundedSender},
+};
+
+mod audio;
+
+mod audio_sink;
+pub use audio_sink::{AudioSink, AudioSpec, NullSink, WavSink};
+
+#[cfg(feature = "audio-device")]
+mod device_sink;
+#[cfg(feature = "audio-device")]
+pub use device_sink::DeviceSink;
+
+mod clock;
+pub use clock::PlaybackClock;
+
+mod color;
+pub use color::{
+    ColorInfo, ColorMatrix, ColorPrimaries, ColorRange, HdrMetadata, TransferFunction,
+};
+
+mod convert;
 
 mod decoder;
-use decoder::loop_decoder;
+use decoder::{loop_decoder, Command};
+
+mod error;
+pub use error::PlayerError;
+
+mod events;
+pub use events::PlayerEvent;
 
 mod frame_buffer;
-use frame_buffer::{Frame, FrameBuffer};
+pub use frame_buffer::{Frame, FrameBuffer, FrameQueue, FrameStats, FRAMES_IN_FLIGHT};
+
+mod frame_pool;
+pub use frame_pool::{FrameData, FramePool};
+
+mod input;
+
+mod keyframe_index;
+pub use keyframe_index::{IndexEntry, KeyframeIndex};
+
+mod media_info;
+pub use media_info::{
+    AudioStreamInfo, ChapterInfo, MediaInfo, StreamInfo, StreamKind, VideoStreamInfo,
+};
 
 mod probe;
-pub use probe::get_video_info;
+pub use probe::{
+    build_keyframe_index, get_media_info, get_video_info, load_keyframe_index, VideoInfo,
+};
 
-#[derive(Default)]
+mod snapshot;
+pub use snapshot::write_png;
+
+mod tempo;
+
+/// Supported playback speed range for `VideoPlayer::set_rate`.
+pub const MIN_RATE: f64 = 0.25;
+pub const MAX_RATE: f64 = 4.0;
+
+/// Layout of `Frame::data`.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
 pub enum PixelFormat {
     #[default]
     RGBA,
+    /// Packed 8-bit RGB without alpha. Expanded to RGBA when uploaded.
     RGB24,
+    /// `RGBA` with red and blue swapped, as many capture sources deliver it.
+    BGRA,
+    /// 8-bit full-range luma only; shown as grayscale.
+    GRAY8,
+    /// Planar Y, U, V with 2x2 chroma subsampling. Converted to RGB on the GPU,
+    /// and taken straight from the decoder without `swscale` when it decodes to it.
+    Yuv420p,
+    /// Y plane followed by interleaved UV at 2x2 subsampling. Passed through
+    /// like `Yuv420p`.
+    Nv12,
+    /// `Nv12` with 16-bit little-endian samples, 10 significant bits at the top.
+    /// `Yuv420p` and `Nv12` switch to this for sources deeper than 8 bits,
+    /// so HDR keeps its precision.
+    P010,
+}
+
+impl PixelFormat {
+    /// (row bytes, rows) of each plane of a `width`x`height` image, in `Frame::data` order.
+    pub fn plane_sizes(self, width: u32, height: u32) -> Vec<(usize, usize)> {
+        let (w, h) = (width as usize, height as usize);
+        let (cw, ch) = (w.div_ceil(2), h.div_ceil(2));
+
+        match self {
+            PixelFormat::RGBA | PixelFormat::BGRA => vec![(4 * w, h)],
+            PixelFormat::RGB24 => vec![(3 * w, h)],
+            PixelFormat::GRAY8 => vec![(w, h)],
+            PixelFormat::Yuv420p => vec![(w, h), (cw, ch), (cw, ch)],
+            PixelFormat::Nv12 => vec![(w, h), (2 * cw, ch)],
+            PixelFormat::P010 => vec![(2 * w, h), (4 * cw, ch)],
+        }
+    }
+
+    /// Converted to RGB on the GPU; the others are converted by `swscale`.
+    #[inline]
+    pub fn is_yuv(self) -> bool {
+        matches!(
+            self,
+            PixelFormat::Yuv420p | PixelFormat::Nv12 | PixelFormat::P010
+        )
+    }
+}
+
+/// Clockwise quarter turns that show a picture upright.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Rotation {
+    #[default]
+    None,
+    Cw90,
+    Cw180,
+    Cw270,
+}
+
+impl Rotation {
+    /// Nearest quarter turn to `degrees` clockwise.
+    pub fn from_degrees(degrees: f64) -> Self {
+        match (degrees / 90.0).round().rem_euclid(4.0) as u8 {
+            1 => Rotation::Cw90,
+            2 => Rotation::Cw180,
+            3 => Rotation::Cw270,
+            _ => Rotation::None,
+        }
+    }
+
+    #[inline]
+    pub fn quarter_turns(self) -> u32 {
+        self as u32
+    }
+
+    /// `self` followed by `other`.
+    pub fn then(self, other: Rotation) -> Self {
+        match (self.quarter_turns() + other.quarter_turns()) % 4 {
+            1 => Rotation::Cw90,
+            2 => Rotation::Cw180,
+            3 => Rotation::Cw270,
+            _ => Rotation::None,
+        }
+    }
+
+    /// Width and height trade places.
+    #[inline]
+    pub fn is_transposed(self) -> bool {
+        matches!(self, Rotation::Cw90 | Rotation::Cw270)
+    }
+}
+
+/// Size of the frames the decoder hands out, relative to the source.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum OutputSize {
+    /// The decoder's own size.
+    #[default]
+    Native,
+    /// Exactly this size. `Frame::sar` makes up for a different aspect ratio.
+    Fixed(u32, u32),
+    /// Scaled down, keeping the aspect ratio, until it fits. Never scaled up.
+    FitWithin(u32, u32),
+    /// Scaled down, keeping the aspect ratio, to at most this many rows.
+    MaxHeight(u32),
+}
+
+impl OutputSize {
+    /// Output dimensions for a `width`x`height` source. Scaled-down sizes are
+    /// even, so 4:2:0 chroma covers whole pixels.
+    pub fn resolve(self, width: u32, height: u32) -> (u32, u32) {
+        if width == 0 || height == 0 {
+            return (width, height);
+        }
+
+        let scale = match self {
+            OutputSize::Native => return (width, height),
+            OutputSize::Fixed(w, h) => return (w.max(1), h.max(1)),
+            OutputSize::FitWithin(w, h) => (w as f64 / width as f64).min(h as f64 / height as f64),
+            OutputSize::MaxHeight(h) => h as f64 / height as f64,
+        };
+        if scale >= 1.0 {
+            return (width, height);
+        }
+
+        // Rounded down to even, so `FitWithin` still fits
+        let scaled = |size: u32| ((size as f64 * scale).round() as u32 & !1).max(2);
+        (scaled(width), scaled(height))
+    }
+}
+
+/// Resampling filter used when frames are resized.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ScaleFilter {
+    #[default]
+    Bilinear,
+    Bicubic,
+    /// Sharpest; slowest.
+    Lanczos,
+    /// Averages the covered source pixels. Good for large reductions.
+    Area,
+    /// Nearest neighbour. Cheapest, blocky.
+    Point,
+}
+
+/// What the decoder does when live input is lost: the connection drops, a read
+/// times out or hits the end.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Reconnect {
+    /// Stop with the error, or at the end of the input.
+    Never,
+    /// Reopen the input at its live edge. The first attempt waits `initial_delay`,
+    /// each further one twice as long up to `max_delay`; playback stops after
+    /// `max_attempts` failed attempts in a row.
+    Backoff {
+        initial_delay: Duration,
+        max_delay: Duration,
+        max_attempts: u32,
+    },
+}
+
+impl Default for Reconnect {
+    fn default() -> Self {
+        Self::attempts(10)
+    }
+}
+
+impl Reconnect {
+    /// Back off from half a second up to 30 seconds, giving up after
+    /// `max_attempts` failures in a row. `Never` for 0.
+    pub fn attempts(max_attempts: u32) -> Self {
+        match max_attempts {
+            0 => Reconnect::Never,
+            max_attempts => Reconnect::Backoff {
+                initial_delay: Duration::from_millis(500),
+                max_delay: Duration::from_secs(30),
+                max_attempts,
+            },
+        }
+    }
+
+    /// Wait before reconnection attempt `attempt` (from 1), `None` once it should give up.
+    pub fn delay(self, attempt: u32) -> Option<Duration> {
+        match self {
+            Reconnect::Never => None,
+            Reconnect::Backoff {
+                initial_delay,
+                max_delay,
+                max_attempts,
+            } => (1..=max_attempts).contains(&attempt).then(|| {
+                let factor = 1u32.checked_shl(attempt - 1).unwrap_or(u32::MAX);
+                initial_delay.saturating_mul(factor).min(max_delay)
+            }),
+        }
+    }
+}
+
+/// How precisely `VideoPlayer::seek` lands on the requested position.
+#[derive(Default, Clone, Copy)]
+pub enum SeekMode {
+    /// Resume from the nearest keyframe at or before the position. Fast.
+    #[default]
+    Keyframe,
+    /// Decode from the keyframe but only show frames from the position onwards.
+    Accurate,
+}
+
+/// What happens when playback reaches the end of the input, or of part of it.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LoopMode {
+    /// Stop at the end.
+    #[default]
+    Off,
+    /// Start over from the beginning at the end of the input.
+    Whole,
+    /// Repeat `a_us..b_us` on the stream timeline (`Frame::stream_ts_us`).
+    /// Behaves like `Off` unless `a_us < b_us`.
+    Range(u64, u64),
+}
+
+/// Where decoded audio is played.
+#[derive(Default)]
+pub enum AudioOutput {
+    /// Default output device, falling back to `Null` if there is none or the
+    /// `audio-device` feature is off.
+    #[default]
+    Device,
+    /// Discard samples in real time; keeps A/V sync on headless machines.
+    Null,
+    /// Write samples to a WAV file in real time.
+    Wav(PathBuf),
+    /// Caller-provided sink.
+    Custom(Box<dyn AudioSink + Send>),
+    /// Ignore the audio stream; video is paced by the wall clock.
+    Disabled,
 }
 
 #[derive(Default)]
 pub struct PlaybackParams {
     pub pixel_format: PixelFormat,
+    /// Show frames as soon as they are decoded instead of pacing them by timestamp.
     pub is_live: bool,
+    pub audio: AudioOutput,
+    /// Where to start, on the stream timeline. Seeks frame-accurately when non-zero.
+    pub start_us: u64,
+    /// Ignored for live input. `Frame::ts_us` keeps increasing across loops.
+    pub loop_mode: LoopMode,
+    /// How many decoded frames may wait for the consumer.
+    pub frame_queue: FrameQueue,
+    /// Idle frame buffers kept for reuse. `None` → enough for the frame queue
+    /// plus `FRAMES_IN_FLIGHT`; `Some(0)` allocates every frame afresh.
+    pub frame_pool_size: Option<usize>,
+    /// Frame size; `Native` avoids resizing. Follows the source if its size changes.
+    pub output_size: OutputSize,
+    pub scale_filter: ScaleFilter,
+    /// Longest a single open, read or seek of the input may block before the
+    /// input counts as lost. `None` waits indefinitely; stopping playback
+    /// interrupts the wait either way.
+    pub read_timeout: Option<Duration>,
+    /// Only used with `is_live`.
+    pub reconnect: Reconnect,
 }
 
 pub struct VideoPlayer {
     frame_buffer: FrameBuffer,
+    clock: PlaybackClock,
+    commands: Option<UnboundedSender<Command>>,
+    events: broadcast::Sender<PlayerEvent>,
     is_initialized: bool,
     shutdown: Arc<AtomicBool>,
 }
 
+/// Events a subscriber may fall behind by before it starts missing them.
+const EVENT_CAPACITY: usize = 64;
+
+impl Default for VideoPlayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl VideoPlayer {
     pub fn new() -> Self {
         Self {
             frame_buffer: FrameBuffer::new(),
+            clock: PlaybackClock::new(),
+            commands: None,
+            events: broadcast::channel(EVENT_CAPACITY).0,
             is_initialized: false,
             shutdown: Arc::new(AtomicBool::new(false)),
         }
@@ -47,18 +366,39 @@ impl VideoPlayer {
         &mut self,
         url: &str,
         params: PlaybackParams,
-    ) -> Result<(), Box<dyn Error>> {
+    ) -> Result<(), PlayerError> {
         if self.is_initialized {
-            return Err("VideoPlayer is already initialized".into());
+            return Err(PlayerError::AlreadyInitialized);
         }
 
         let shutdown_clone = self.shutdown.clone();
+        let (commands_tx, commands_rx) = unbounded_channel();
+        let pool_size = params
+            .frame_pool_size
+            .unwrap_or(params.frame_queue.depth() + FRAMES_IN_FLIGHT);
+        self.frame_buffer = FrameBuffer::with_queue(params.frame_queue, pool_size);
+        self.commands = Some(commands_tx);
         self.is_initialized = true;
 
         tokio::task::spawn_blocking({
             let url = url.to_string();
             let buffer = self.frame_buffer.clone();
-            move || loop_decoder(url, params, buffer, shutdown_clone)
+            let clock = self.clock.clone();
+            let events = self.events.clone();
+            move || {
+                if let Err(e) = loop_decoder(
+                    url,
+                    params,
+                    buffer.clone(),
+                    clock,
+                    commands_rx,
+                    events.clone(),
+                    shutdown_clone,
+                ) {
+                    let _ = events.send(PlayerEvent::Error(e));
+                    buffer.finish();
+                }
+            }
         });
 
         Ok(())
@@ -70,12 +410,111 @@ impl VideoPlayer {
         }
 
         self.shutdown.store(true, Ordering::Relaxed);
+        self.commands = None;
         self.is_initialized = false;
     }
 
+    /// Jump to `position_us` on the timeline of `Frame::ts_us`, which is the
+    /// stream timeline until playback loops.
+    pub fn seek(&self, position_us: u64, mode: SeekMode) -> Result<(), PlayerError> {
+        self.send_command(Command::Seek { position_us, mode })
+    }
+
+    /// Change what happens at the end of the input while playing.
+    pub fn set_loop_mode(&self, mode: LoopMode) -> Result<(), PlayerError> {
+        self.send_command(Command::SetLoop(mode))
+    }
+
+    /// Subscribe to state changes. Each receiver sees the events sent after it was created.
+    pub fn events(&self) -> broadcast::Receiver<PlayerEvent> {
+        self.events.subscribe()
+    }
+
+    pub fn pause(&self) {
+        if !self.clock.is_paused() {
+            self.clock.pause();
+            let _ = self.events.send(PlayerEvent::Paused);
+        }
+    }
+
+    pub fn resume(&self) {
+        if self.clock.is_paused() {
+            self.clock.resume();
+            let _ = self.events.send(PlayerEvent::Playing);
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.clock.is_paused()
+    }
+
+    /// Pause and show the next frame.
+    pub fn step_forward(&self) -> Result<(), PlayerError> {
+        self.pause();
+        self.send_command(Command::StepForward)
+    }
+
+    /// Pause and show the frame before the current one. This seeks back to the
+    /// previous keyframe and decodes forward, so it is slower than stepping forward.
+    pub fn step_backward(&self) -> Result<(), PlayerError> {
+        self.pause();
+        self.send_command(Command::StepBackward)
+    }
+
+    /// Set the playback speed, clamped to `MIN_RATE..=MAX_RATE`.
+    /// Audio is time-stretched to the new rate, keeping its pitch.
+    pub fn set_rate(&self, rate: f64) {
+        self.clock.set_rate(rate.clamp(MIN_RATE, MAX_RATE));
+    }
+
+    pub fn rate(&self) -> f64 {
+        self.clock.rate()
+    }
+
+    /// The clock that paces both decoding and presentation.
+    pub fn clock(&self) -> PlaybackClock {
+        self.clock.clone()
+    }
+
+    /// Take the newest frame the decoder has shown, regardless of the clock.
+    /// Older queued frames are skipped and counted in `FrameStats::dropped_late`.
     pub fn get_latest_frame(&mut self) -> Option<Arc<Frame>> {
+        self.frame_buffer.consume_latest()
+    }
+
+    /// Take the oldest frame the decoder has shown, regardless of the clock.
+    pub fn next_queued_frame(&mut self) -> Option<Arc<Frame>> {
         self.frame_buffer.consume()
     }
+
+    /// Take the newest frame that is due on the playback clock. Earlier frames
+    /// that are due too are skipped and counted in `FrameStats::dropped_late`.
+    pub fn next_due_frame(&mut self) -> Option<Arc<Frame>> {
+        self.frame_buffer.consume_due(self.clock.now_us())
+    }
+
+    /// Frame counters since playback started.
+    pub fn stats(&self) -> FrameStats {
+        self.frame_buffer.stats()
+    }
+
+    /// Wait for the decoder to show another frame. `None` when the pending frame
+    /// was dropped, e.g. by a seek; playback may still continue. A paced bounded
+    /// queue hands frames out ahead of the clock: hold each until its `ts_us`.
+    pub async fn next_frame(&mut self) -> Option<Arc<Frame>> {
+        self.frame_buffer.get_next().await
+    }
+
+    /// Save a decoded frame as a PNG at its native resolution, independent of
+    /// how it is scaled or tone mapped on screen.
+    pub fn save_frame(frame: &Frame, path: &Path) -> Result<(), PlayerError> {
+        snapshot::save_png(frame, path)
+    }
+
+    fn send_command(&self, command: Command) -> Result<(), PlayerError> {
+        let commands = self.commands.as_ref().ok_or(PlayerError::NotInitialized)?;
+        commands.send(command).map_err(|_| PlayerError::NotRunning)
+    }
 }
 
 impl Drop for VideoPlayer {
@@ -83,3 +522,128 @@ impl Drop for VideoPlayer {
         self.shutdown.store(true, Ordering::Relaxed);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plane_sizes_round_chroma_up() {
+        let sizes = |format: PixelFormat| format.plane_sizes(5, 3);
+        assert_eq!(sizes(PixelFormat::RGBA), [(20, 3)]);
+        assert_eq!(sizes(PixelFormat::BGRA), [(20, 3)]);
+        assert_eq!(sizes(PixelFormat::RGB24), [(15, 3)]);
+        assert_eq!(sizes(PixelFormat::GRAY8), [(5, 3)]);
+        assert_eq!(sizes(PixelFormat::Yuv420p), [(5, 3), (3, 2), (3, 2)]);
+        assert_eq!(sizes(PixelFormat::Nv12), [(5, 3), (6, 2)]);
+        assert_eq!(sizes(PixelFormat::P010), [(10, 3), (12, 2)]);
+    }
+
+    #[test]
+    fn rotation_from_degrees_normalizes() {
+        assert_eq!(Rotation::from_degrees(0.0), Rotation::None);
+        assert_eq!(Rotation::from_degrees(90.0), Rotation::Cw90);
+        assert_eq!(Rotation::from_degrees(180.0), Rotation::Cw180);
+        assert_eq!(Rotation::from_degrees(270.0), Rotation::Cw270);
+        // Counterclockwise and past a full turn
+        assert_eq!(Rotation::from_degrees(-90.0), Rotation::Cw270);
+        assert_eq!(Rotation::from_degrees(-180.0), Rotation::Cw180);
+        assert_eq!(Rotation::from_degrees(-450.0), Rotation::Cw270);
+        assert_eq!(Rotation::from_degrees(360.0), Rotation::None);
+        assert_eq!(Rotation::from_degrees(450.0), Rotation::Cw90);
+        // Nearest quarter turn
+        assert_eq!(Rotation::from_degrees(89.5), Rotation::Cw90);
+        assert_eq!(Rotation::from_degrees(359.0), Rotation::None);
+        assert_eq!(Rotation::from_degrees(f64::NAN), Rotation::None);
+    }
+
+    #[test]
+    fn rotation_then_adds_quarter_turns() {
+        assert_eq!(Rotation::None.then(Rotation::Cw90), Rotation::Cw90);
+        assert_eq!(Rotation::Cw90.then(Rotation::Cw90), Rotation::Cw180);
+        assert_eq!(Rotation::Cw90.then(Rotation::Cw270), Rotation::None);
+        assert_eq!(Rotation::Cw270.then(Rotation::Cw180), Rotation::Cw90);
+        assert_eq!(Rotation::Cw180.then(Rotation::Cw180), Rotation::None);
+    }
+
+    #[test]
+    fn output_size_fixed_ignores_source() {
+        assert_eq!(OutputSize::Fixed(640, 360).resolve(1920, 1080), (640, 360));
+        assert_eq!(
+            OutputSize::Fixed(4000, 3000).resolve(1920, 1080),
+            (4000, 3000)
+        );
+        assert_eq!(OutputSize::Native.resolve(1921, 1081), (1921, 1081));
+    }
+
+    #[test]
+    fn output_size_fit_within_keeps_aspect() {
+        // Limited by width, then by height
+        assert_eq!(
+            OutputSize::FitWithin(1280, 1280).resolve(1920, 1080),
+            (1280, 720)
+        );
+        assert_eq!(
+            OutputSize::FitWithin(1920, 540).resolve(1920, 1080),
+            (960, 540)
+        );
+        // Never scaled up
+        assert_eq!(
+            OutputSize::FitWithin(3840, 2160).resolve(1280, 720),
+            (1280, 720)
+        );
+    }
+
+    #[test]
+    fn output_size_scaled_dimensions_are_even() {
+        // 1000x562.5 and 1001x563 before rounding
+        assert_eq!(
+            OutputSize::FitWithin(1000, 1000).resolve(1920, 1080),
+            (1000, 562)
+        );
+        assert_eq!(
+            OutputSize::FitWithin(1001, 1001).resolve(1920, 1080),
+            (1000, 562)
+        );
+        assert_eq!(OutputSize::MaxHeight(481).resolve(1280, 721), (854, 480));
+    }
+
+    #[test]
+    fn output_size_max_height() {
+        assert_eq!(OutputSize::MaxHeight(720).resolve(1920, 1080), (1280, 720));
+        assert_eq!(OutputSize::MaxHeight(720).resolve(640, 480), (640, 480));
+        // Portrait: rows, not the shorter side
+        assert_eq!(OutputSize::MaxHeight(960).resolve(1080, 1920), (540, 960));
+    }
+
+    #[test]
+    fn reconnect_delay_doubles_up_to_max() {
+        let policy = Reconnect::Backoff {
+            initial_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(1),
+            max_attempts: 6,
+        };
+        let delays: Vec<_> = (1..=6)
+            .map(|attempt| policy.delay(attempt).unwrap())
+            .collect();
+        let expected = [100, 200, 400, 800, 1000, 1000].map(Duration::from_millis);
+        assert_eq!(delays, expected);
+    }
+
+    #[test]
+    fn reconnect_delay_gives_up_after_max_attempts() {
+        let policy = Reconnect::attempts(3);
+        assert_eq!(policy.delay(0), None);
+        assert!(policy.delay(3).is_some());
+        assert_eq!(policy.delay(4), None);
+        assert_eq!(Reconnect::attempts(0), Reconnect::Never);
+        assert_eq!(Reconnect::Never.delay(1), None);
+    }
+
+    #[test]
+    fn reconnect_delay_saturates() {
+        let policy = Reconnect::attempts(u32::MAX);
+        assert_eq!(policy.delay(40), Some(Duration::from_secs(30)));
+        assert_eq!(policy.delay(u32::MAX), Some(Duration::from_secs(30)));
+    }
+}
@@ -1,8 +1,30 @@
-use ffmpeg::{codec::context::Context, ffi::AV_TIME_BASE, format, media::Type};
+use crate::video_player::{
+    convert::stream_hdr_metadata,
+    decoder::{open_video_decoder, pts_to_us},
+    error::PlayerError,
+    keyframe_index::{IndexEntry, KeyframeIndex},
+    media_info::{
+        AudioStreamInfo, ChapterInfo, MediaInfo, StreamInfo, StreamKind, VideoStreamInfo,
+    },
+};
+use ffmpeg::{
+    codec::{self, packet},
+    ffi::{
+        av_channel_layout_describe, av_display_rotation_get, avcodec_descriptor_get,
+        avcodec_profile_name, AVChannelLayout, AV_NOPTS_VALUE, AV_TIME_BASE,
+    },
+    format::{self, context::Input, stream::Disposition},
+    media::Type,
+    util::{
+        color::{Primaries, Range, Space, TransferCharacteristic},
+        format::{Pixel, Sample},
+    },
+    DictionaryRef, Rational, Stream,
+};
 use ffmpeg_next as ffmpeg;
-use std::error::Error;
+use std::{collections::BTreeMap, ffi::CStr, os::raw::c_char, path::Path};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct VideoInfo {
     pub width: u32,
     pub height: u32,
@@ -10,37 +32,258 @@ pub struct VideoInfo {
     pub avg_fps: Option<f64>,
 }
 
-pub fn get_video_info(input: &str) -> Result<VideoInfo, Box<dyn Error>> {
-    ffmpeg::init()?;
-    let ictx = format::input(input)?;
+pub fn get_video_info(input: &str) -> Result<VideoInfo, PlayerError> {
+    ffmpeg::init().map_err(|e| PlayerError::open(input, e))?;
+    let ictx = format::input(input).map_err(|e| PlayerError::open(input, e))?;
+    video_info(&ictx)
+}
+
+/// Describe the best video stream of an already opened input.
+pub fn video_info(ictx: &Input) -> Result<VideoInfo, PlayerError> {
     let vstream = ictx
         .streams()
         .best(Type::Video)
-        .ok_or_else(|| "No video stream")?;
+        .ok_or(PlayerError::NoVideoStream)?;
 
-    let ctx = Context::from_parameters(vstream.parameters())?;
-    let dec = ctx.decoder().video()?;
+    let dec = open_video_decoder(&vstream)?;
 
     let width = dec.width();
     let height = dec.height();
 
-    let fps = vstream.avg_frame_rate();
-    let avg_fps = if fps.1 != 0 {
-        Some(fps.0 as f64 / fps.1 as f64)
-    } else {
-        None
+    Ok(VideoInfo {
+        width,
+        height,
+        duration_us: container_duration_us(ictx),
+        avg_fps: frame_rate(vstream.avg_frame_rate()),
+    })
+}
+
+pub fn get_media_info(input: &str) -> Result<MediaInfo, PlayerError> {
+    ffmpeg::init().map_err(|e| PlayerError::open(input, e))?;
+    let ictx = format::input(input).map_err(|e| PlayerError::open(input, e))?;
+    media_info(&ictx)
+}
+
+/// Describe an already opened input and all of its streams.
+pub fn media_info(ictx: &Input) -> Result<MediaInfo, PlayerError> {
+    let format = ictx.format();
+
+    let chapters = ictx
+        .chapters()
+        .map(|chapter| {
+            let tb = chapter.time_base();
+            let metadata = dictionary(chapter.metadata());
+            ChapterInfo {
+                id: chapter.id(),
+                start_us: to_us(chapter.start(), tb),
+                end_us: to_us(chapter.end(), tb),
+                title: metadata.get("title").cloned(),
+                metadata,
+            }
+        })
+        .collect();
+
+    let streams = ictx
+        .streams()
+        .map(|stream| stream_info(&stream))
+        .collect::<Result<_, _>>()?;
+
+    Ok(MediaInfo {
+        format_name: format.name().to_string(),
+        format_long_name: format.description().to_string(),
+        duration_us: container_duration_us(ictx),
+        bit_rate: (ictx.bit_rate() > 0).then_some(ictx.bit_rate()),
+        metadata: dictionary(ictx.metadata()),
+        chapters,
+        streams,
+    })
+}
+
+fn stream_info(stream: &Stream) -> Result<StreamInfo, PlayerError> {
+    let parameters = stream.parameters();
+    let id = parameters.id();
+    let kind = match parameters.medium() {
+        Type::Video => StreamKind::Video,
+        Type::Audio => StreamKind::Audio,
+        Type::Subtitle => StreamKind::Subtitle,
+        Type::Data => StreamKind::Data,
+        Type::Attachment => StreamKind::Attachment,
+        Type::Unknown => StreamKind::Unknown,
     };
 
-    let duration_us = if ictx.duration() > 0 {
-        Some((ictx.duration() as i128 * 1_000_000i128 / AV_TIME_BASE as i128) as i64)
-    } else {
-        None
+    // Typed access to the parameters, without needing a decoder for the codec
+    let ctx = codec::Context::from_parameters(parameters).map_err(PlayerError::Decoder)?;
+    let raw = unsafe { &*ctx.as_ptr() };
+    let metadata = dictionary(stream.metadata());
+
+    let video = (kind == StreamKind::Video).then(|| VideoStreamInfo {
+        width: raw.width as u32,
+        height: raw.height as u32,
+        pixel_format: Pixel::from(raw.pix_fmt)
+            .descriptor()
+            .map(|desc| desc.name().to_string()),
+        avg_fps: frame_rate(stream.avg_frame_rate()),
+        sample_aspect_ratio: Some(raw.sample_aspect_ratio)
+            .filter(|sar| sar.num > 0 && sar.den > 0)
+            .map(|sar| (sar.num, sar.den)),
+        rotation: stream_rotation(stream),
+        color_space: Space::from(raw.colorspace).name().map(str::to_string),
+        color_range: Range::from(raw.color_range).name().map(str::to_string),
+        color_primaries: Primaries::from(raw.color_primaries)
+            .name()
+            .map(str::to_string),
+        color_transfer: TransferCharacteristic::from(raw.color_trc)
+            .name()
+            .map(str::to_string),
+        hdr: stream_hdr_metadata(stream),
+    });
+
+    let audio = (kind == StreamKind::Audio).then(|| AudioStreamInfo {
+        sample_rate: raw.sample_rate as u32,
+        channels: raw.ch_layout.nb_channels as u32,
+        channel_layout: channel_layout_name(&raw.ch_layout),
+        sample_format: match Sample::from(raw.sample_fmt) {
+            Sample::None => None,
+            format => Some(format.name().to_string()),
+        },
+    });
+
+    let (codec_long_name, profile) = unsafe {
+        (
+            avcodec_descriptor_get(id.into())
+                .as_ref()
+                .and_then(|desc| c_string(desc.long_name)),
+            c_string(avcodec_profile_name(id.into(), raw.profile)),
+        )
     };
 
-    Ok(VideoInfo {
-        width,
-        height,
-        duration_us,
-        avg_fps,
+    Ok(StreamInfo {
+        index: stream.index(),
+        kind,
+        codec: id.name().to_string(),
+        codec_long_name,
+        profile,
+        // FF_LEVEL_UNKNOWN is negative
+        level: (raw.level >= 0).then_some(raw.level),
+        bit_rate: (raw.bit_rate > 0).then_some(raw.bit_rate),
+        duration_us: (stream.duration() != AV_NOPTS_VALUE)
+            .then(|| to_us(stream.duration(), stream.time_base())),
+        language: metadata.get("language").cloned(),
+        is_default: stream.disposition().contains(Disposition::DEFAULT),
+        metadata,
+        video,
+        audio,
     })
 }
+
+/// Demux the best video stream without decoding it and list every packet.
+pub fn build_keyframe_index(input: &str) -> Result<KeyframeIndex, PlayerError> {
+    ffmpeg::init().map_err(|e| PlayerError::open(input, e))?;
+    let mut ictx = format::input(input).map_err(|e| PlayerError::open(input, e))?;
+
+    let vstream = ictx
+        .streams()
+        .best(Type::Video)
+        .ok_or(PlayerError::NoVideoStream)?;
+    let (vindex, tb) = (vstream.index(), vstream.time_base());
+
+    let mut entries = Vec::new();
+    for (stream, packet) in ictx.packets() {
+        if stream.index() != vindex {
+            continue;
+        }
+        let Some(ts) = packet.pts().or(packet.dts()) else {
+            continue;
+        };
+
+        entries.push(IndexEntry {
+            pts_us: to_us(ts, tb),
+            byte_pos: u64::try_from(packet.position()).ok(),
+            is_keyframe: packet.is_key(),
+            size: packet.size() as u32,
+        });
+    }
+
+    Ok(KeyframeIndex { entries })
+}
+
+/// `build_keyframe_index`, cached next to local files (see
+/// `KeyframeIndex::sidecar_path`) and rebuilt when the file changes.
+/// URLs are indexed from scratch every time.
+pub fn load_keyframe_index(input: &str) -> Result<KeyframeIndex, PlayerError> {
+    let path = Path::new(input);
+    if !path.is_file() {
+        return build_keyframe_index(input);
+    }
+
+    let sidecar = KeyframeIndex::sidecar_path(path);
+    match KeyframeIndex::load(&sidecar, path) {
+        Ok(Some(index)) => return Ok(index),
+        Ok(None) => {}
+        Err(e) => eprintln!("Ignoring {}: {e}", sidecar.display()),
+    }
+
+    let index = build_keyframe_index(input)?;
+    // e.g. a read-only directory → just don't cache
+    if let Err(e) = index.save(&sidecar, path) {
+        eprintln!("Failed to write {}: {e}", sidecar.display());
+    }
+    Ok(index)
+}
+
+/// Clockwise rotation from the stream's display matrix, in degrees.
+pub(crate) fn stream_rotation(stream: &Stream) -> Option<f64> {
+    let side_data = stream
+        .side_data()
+        .find(|side_data| side_data.kind() == packet::side_data::Type::DisplayMatrix)?;
+
+    let matrix: Vec<i32> = side_data
+        .data()
+        .chunks_exact(4)
+        .map(|b| i32::from_ne_bytes([b[0], b[1], b[2], b[3]]))
+        .collect();
+    if matrix.len() < 9 {
+        return None;
+    }
+
+    // Counterclockwise in -180..=180
+    let angle = unsafe { av_display_rotation_get(matrix.as_ptr()) };
+    (!angle.is_nan()).then(|| (360.0 - angle) % 360.0)
+}
+
+fn container_duration_us(ictx: &Input) -> Option<i64> {
+    (ictx.duration() > 0)
+        .then(|| (ictx.duration() as i128 * 1_000_000i128 / AV_TIME_BASE as i128) as i64)
+}
+
+fn frame_rate(rate: Rational) -> Option<f64> {
+    (rate.1 != 0).then(|| rate.0 as f64 / rate.1 as f64)
+}
+
+#[inline]
+fn to_us(ts: i64, tb: Rational) -> i64 {
+    pts_to_us(ts, tb.0 as u32, tb.1 as u32).unwrap_or(0)
+}
+
+fn dictionary(dict: DictionaryRef) -> BTreeMap<String, String> {
+    dict.iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+fn channel_layout_name(layout: &AVChannelLayout) -> Option<String> {
+    if layout.nb_channels == 0 {
+        return None;
+    }
+    let mut buf = [0 as c_char; 64];
+    match unsafe { av_channel_layout_describe(layout, buf.as_mut_ptr(), buf.len()) } {
+        len if len < 0 => None,
+        _ => unsafe { c_string(buf.as_ptr()) },
+    }
+}
+
+/// Copy a string owned by FFmpeg; `None` for null pointers.
+unsafe fn c_string(ptr: *const c_char) -> Option<String> {
+    ptr.as_ref()
+        .map(|_| CStr::from_ptr(ptr).to_string_lossy().into_owned())
+}
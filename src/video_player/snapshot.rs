@@ -0,0 +1,72 @@
+use crate::video_player::{
+    convert::{ffmpeg_pixel, set_colorspace},
+    error::PlayerError,
+    frame_buffer::Frame,
+};
+use ffmpeg::{
+    software::scaling::{Context as Scaler, Flags},
+    util::{format::Pixel, frame::Video},
+};
+use ffmpeg_next as ffmpeg;
+use std::{
+    fs::File,
+    io::{self, BufWriter},
+    path::Path,
+};
+
+/// Write `frame` as a PNG at its native resolution. YUV is converted with the
+/// frame's own matrix and range; HDR frames are not tone mapped.
+pub fn save_png(frame: &Frame, path: &Path) -> Result<(), PlayerError> {
+    let rgba = to_rgba(frame).map_err(PlayerError::Scaler)?;
+    write_png(path, frame.width, frame.height, &rgba)
+        .map_err(|e| PlayerError::Snapshot(format!("{}: {e}", path.display())))
+}
+
+/// Write tightly packed 8-bit sRGB RGBA rows as a PNG.
+pub fn write_png(path: &Path, width: u32, height: u32, rgba: &[u8]) -> io::Result<()> {
+    let mut encoder = png::Encoder::new(BufWriter::new(File::create(path)?), width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()?.write_image_data(rgba)?;
+    Ok(())
+}
+
+fn to_rgba(frame: &Frame) -> Result<Vec<u8>, ffmpeg::Error> {
+    let (w, h) = (frame.width, frame.height);
+
+    let mut src = Video::new(ffmpeg_pixel(frame.format), w, h);
+    for (i, (row, _)) in frame.format.plane_sizes(w, h).into_iter().enumerate() {
+        let Some(plane) = frame.plane(i) else {
+            break;
+        };
+        let stride = src.stride(i);
+        for (dst, line) in src
+            .data_mut(i)
+            .chunks_mut(stride)
+            .zip(plane.chunks(frame.stride[i]))
+        {
+            dst[..row].copy_from_slice(&line[..row]);
+        }
+    }
+
+    let mut scaler = Scaler::get(
+        src.format(),
+        w,
+        h,
+        Pixel::RGBA,
+        w,
+        h,
+        Flags::BICUBIC | Flags::FULL_CHR_H_INT | Flags::ACCURATE_RND,
+    )?;
+    set_colorspace(&mut scaler, frame.color, true);
+
+    let mut out = Video::empty();
+    scaler.run(&src, &mut out)?;
+
+    let row = 4 * w as usize;
+    let mut rgba = Vec::with_capacity(row * h as usize);
+    for line in out.data(0).chunks(out.stride(0)).take(h as usize) {
+        rgba.extend_from_slice(&line[..row]);
+    }
+    Ok(rgba)
+}
@@ -0,0 +1,86 @@
+use crate::video_player::audio_sink::AudioSpec;
+use ffmpeg::{filter, util::frame::Audio, ChannelLayout};
+use ffmpeg_next as ffmpeg;
+
+/// Slowest and fastest factor of a single `atempo` instance that every FFmpeg
+/// version accepts; rates outside are built from several.
+const ATEMPO_RANGE: (f64, f64) = (0.5, 2.0);
+
+/// Plays interleaved f32 audio faster or slower without changing its pitch,
+/// through FFmpeg's `atempo` filter.
+///
+/// Output sample `n` corresponds to input sample `n * rate`: the filter only
+/// delays it, so one timestamp for its first sample maps all output to media time.
+pub struct Tempo {
+    graph: filter::Graph,
+}
+
+impl Tempo {
+    pub fn new(rate: f64, spec: AudioSpec) -> Result<Self, ffmpeg::Error> {
+        let mut graph = filter::Graph::new();
+        let args = format!(
+            "time_base=1/{0}:sample_rate={0}:sample_fmt=flt:channel_layout=0x{1:x}",
+            spec.sample_rate,
+            ChannelLayout::default(spec.channels as i32).bits(),
+        );
+        let abuffer = filter::find("abuffer").ok_or(ffmpeg::Error::FilterNotFound)?;
+        let abuffersink = filter::find("abuffersink").ok_or(ffmpeg::Error::FilterNotFound)?;
+        graph.add(&abuffer, "in", &args)?;
+        graph.add(&abuffersink, "out", "")?;
+        graph
+            .output("in", 0)?
+            .input("out", 0)?
+            .parse(&atempo_chain(rate))?;
+        graph.validate()?;
+
+        Ok(Self { graph })
+    }
+
+    /// Feed `input`, or the end of the audio if `None`, and return what the
+    /// filter has ready.
+    pub fn run(&mut self, input: Option<&Audio>) -> Result<Vec<Audio>, ffmpeg::Error> {
+        let mut source = self.graph.get("in").ok_or(ffmpeg::Error::FilterNotFound)?;
+        match input {
+            Some(input) => source.source().add(input)?,
+            None => source.source().flush()?,
+        }
+
+        let mut sink = self.graph.get("out").ok_or(ffmpeg::Error::FilterNotFound)?;
+        let mut ready = Vec::new();
+        let mut out = Audio::empty();
+        // EAGAIN → needs more input; EOF → drained
+        while sink.sink().frame(&mut out).is_ok() {
+            ready.push(std::mem::replace(&mut out, Audio::empty()));
+        }
+        Ok(ready)
+    }
+}
+
+/// `atempo` filters whose factors multiply to `rate`.
+fn atempo_chain(mut rate: f64) -> String {
+    let (min, max) = ATEMPO_RANGE;
+    let mut chain = Vec::new();
+    while rate < min {
+        chain.push(format!("atempo={min}"));
+        rate /= min;
+    }
+    while rate > max {
+        chain.push(format!("atempo={max}"));
+        rate /= max;
+    }
+    chain.push(format!("atempo={rate}"));
+    chain.join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn atempo_chain_splits_out_of_range_rates() {
+        assert_eq!(atempo_chain(1.5), "atempo=1.5");
+        assert_eq!(atempo_chain(0.25), "atempo=0.5,atempo=0.5");
+        assert_eq!(atempo_chain(3.0), "atempo=2,atempo=1.5");
+        assert_eq!(atempo_chain(4.0), "atempo=2,atempo=2");
+    }
+}
@@ -0,0 +1,118 @@
+//! A live TCP input that hangs up after a second of video is reopened.
+
+use ffmpeg::{codec, encoder, format, format::Pixel, util::frame::Video, Packet, Rational};
+use ffmpeg_next as ffmpeg;
+use rs_wgpu_video_player::{
+    video_player::{AudioOutput, PlayerEvent, Reconnect},
+    PlaybackParams, VideoPlayer,
+};
+use std::{io::Write, net::TcpListener, time::Duration};
+use tokio::{sync::broadcast::error::RecvError, time::timeout};
+
+const WIDTH: u32 = 64;
+const HEIGHT: u32 = 64;
+const FPS: i32 = 25;
+
+/// One second of grey MPEG-2 video in an MPEG-TS.
+fn short_ts() -> Vec<u8> {
+    ffmpeg::init().unwrap();
+    let path = std::env::temp_dir().join(format!("reconnect-{}.ts", std::process::id()));
+    let mut octx = format::output_as(&path, "mpegts").unwrap();
+
+    let codec = encoder::find(codec::Id::MPEG2VIDEO).expect("MPEG-2 encoder");
+    let mut enc = codec::context::Context::new_with_codec(codec)
+        .encoder()
+        .video()
+        .unwrap();
+    enc.set_width(WIDTH);
+    enc.set_height(HEIGHT);
+    enc.set_format(Pixel::YUV420P);
+    enc.set_time_base((1, FPS));
+    enc.set_frame_rate(Some((FPS, 1)));
+    let mut enc = enc.open_as(codec).unwrap();
+    octx.add_stream(codec).unwrap().set_parameters(&enc);
+    octx.write_header().unwrap();
+    let enc_tb = Rational::new(1, FPS);
+    let stream_tb = octx.stream(0).unwrap().time_base();
+
+    let mut frame = Video::new(Pixel::YUV420P, WIDTH, HEIGHT);
+    for plane in 0..3 {
+        frame.data_mut(plane).fill(128);
+    }
+    for pts in 0..FPS as i64 {
+        frame.set_pts(Some(pts));
+        enc.send_frame(&frame).unwrap();
+        write_packets(&mut enc, &mut octx, enc_tb, stream_tb);
+    }
+    enc.send_eof().unwrap();
+    write_packets(&mut enc, &mut octx, enc_tb, stream_tb);
+    octx.write_trailer().unwrap();
+
+    let ts = std::fs::read(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+    ts
+}
+
+fn write_packets(
+    enc: &mut encoder::Video,
+    octx: &mut format::context::Output,
+    enc_tb: Rational,
+    stream_tb: Rational,
+) {
+    let mut packet = Packet::empty();
+    while enc.receive_packet(&mut packet).is_ok() {
+        packet.set_stream(0);
+        packet.rescale_ts(enc_tb, stream_tb);
+        packet.write_interleaved(octx).unwrap();
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn reopens_live_input_after_it_closes() {
+    let ts = short_ts();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("tcp://{}", listener.local_addr().unwrap());
+    // Every connection gets the whole stream, then the server hangs up.
+    std::thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            let _ = stream.write_all(&ts);
+        }
+    });
+
+    let mut player = VideoPlayer::new();
+    let mut events = player.events();
+    let params = PlaybackParams {
+        is_live: true,
+        audio: AudioOutput::Disabled,
+        read_timeout: Some(Duration::from_secs(5)),
+        reconnect: Reconnect::Backoff {
+            initial_delay: Duration::from_millis(10),
+            max_delay: Duration::from_millis(100),
+            max_attempts: 5,
+        },
+        ..Default::default()
+    };
+    player.start_playback(&url, params).await.unwrap();
+
+    let mut reconnecting = false;
+    let result = timeout(Duration::from_secs(20), async {
+        loop {
+            match events.recv().await {
+                Ok(PlayerEvent::Reconnecting { attempt, .. }) => {
+                    assert_eq!(attempt, 1);
+                    reconnecting = true;
+                }
+                Ok(PlayerEvent::Reconnected) => return,
+                Ok(PlayerEvent::Error(e)) => panic!("playback failed: {e}"),
+                Ok(PlayerEvent::EndOfStream) => panic!("stopped at the end of live input"),
+                Ok(_) | Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => panic!("player dropped its events"),
+            }
+        }
+    })
+    .await;
+    player.stop_playback();
+
+    assert!(result.is_ok(), "no reconnect within 20 s");
+    assert!(reconnecting, "Reconnected without Reconnecting first");
+}
@@ -0,0 +1,135 @@
+//! Every `PixelFormat` renders a solid frame in the right color.
+#![cfg(feature = "render")]
+
+use rs_wgpu_video_player::{
+    video_player::{
+        ColorInfo, ColorMatrix, ColorPrimaries, ColorRange, PixelFormat, Rotation, TransferFunction,
+    },
+    Frame, Renderer,
+};
+
+const SIZE: u32 = 16;
+/// Decoders pad rows; so do these frames.
+const PADDING: usize = 8;
+/// sRGB orange, and the same in full-range BT.709 YCbCr (118, 92, 180).
+const ORANGE: [u8; 3] = [200, 100, 50];
+
+/// Repeating bytes of one pixel, per plane.
+type Pixels<'a> = &'a [&'a [u8]];
+
+/// Each plane of `format` filled with its repeating `pixels` bytes.
+fn solid_frame(format: PixelFormat, pixels: Pixels) -> Frame {
+    let color = ColorInfo {
+        matrix: ColorMatrix::Bt709,
+        range: ColorRange::Full,
+        ..ColorInfo::default()
+    };
+    solid_frame_in(format, color, pixels)
+}
+
+fn solid_frame_in(format: PixelFormat, color: ColorInfo, pixels: Pixels) -> Frame {
+    let mut stride = [0; 3];
+    let mut data = Vec::new();
+    for (i, (row, rows)) in format.plane_sizes(SIZE, SIZE).into_iter().enumerate() {
+        stride[i] = row + PADDING;
+        for _ in 0..rows {
+            data.extend(pixels[i].iter().cycle().take(row));
+            data.extend([0xEE; PADDING]);
+        }
+    }
+    Frame {
+        width: SIZE,
+        height: SIZE,
+        format,
+        color,
+        data: data.into(),
+        stride,
+        rotation: Rotation::None,
+        sar: 1.0,
+        ts_us: 0,
+        stream_ts_us: 0,
+    }
+}
+
+/// A headless renderer at the frame size, `None` on machines without any adapter.
+async fn renderer() -> Option<Renderer<'static>> {
+    match Renderer::new_headless(SIZE, SIZE).await {
+        Ok(mut renderer) => {
+            renderer.set_video_size(SIZE, SIZE);
+            Some(renderer)
+        }
+        Err(e) => {
+            eprintln!("Skipped, no adapter: {e}");
+            None
+        }
+    }
+}
+
+/// RGB of the center pixel after rendering `frame`.
+fn render_center(renderer: &mut Renderer, frame: &Frame) -> [u8; 3] {
+    renderer.set_frame(frame);
+    let rgba = renderer.render_to_rgba().unwrap();
+    assert_eq!(rgba.len(), (SIZE * SIZE * 4) as usize, "{:?}", frame.format);
+    let center = ((SIZE / 2 * SIZE + SIZE / 2) * 4) as usize;
+    [rgba[center], rgba[center + 1], rgba[center + 2]]
+}
+
+fn assert_close(format: PixelFormat, rgb: [u8; 3], expected: [u8; 3]) {
+    let close = rgb.iter().zip(expected).all(|(&a, b)| a.abs_diff(b) <= 3);
+    assert!(close, "{format:?}: got {rgb:?}, expected {expected:?}");
+}
+
+#[tokio::test]
+async fn every_pixel_format_renders() {
+    let Some(mut renderer) = renderer().await else {
+        return;
+    };
+
+    // 10-bit P010 samples sit in the top bits of 16-bit little-endian words
+    let cases: [(PixelFormat, Pixels, [u8; 3]); 7] = [
+        (PixelFormat::RGBA, &[&[200, 100, 50, 255]], ORANGE),
+        (PixelFormat::RGB24, &[&[200, 100, 50]], ORANGE),
+        (PixelFormat::BGRA, &[&[50, 100, 200, 255]], ORANGE),
+        (PixelFormat::GRAY8, &[&[128]], [128; 3]),
+        (PixelFormat::Yuv420p, &[&[118], &[92], &[180]], ORANGE),
+        (PixelFormat::Nv12, &[&[118], &[92, 180]], ORANGE),
+        (
+            PixelFormat::P010,
+            &[&[0x00, 0x76], &[0x00, 0x5C, 0x00, 0xB4]],
+            ORANGE,
+        ),
+    ];
+    for (format, pixels, expected) in cases {
+        let rgb = render_center(&mut renderer, &solid_frame(format, pixels));
+        assert_close(format, rgb, expected);
+    }
+}
+
+#[tokio::test]
+async fn hdr_rgb_is_tone_mapped_like_yuv() {
+    let Some(mut renderer) = renderer().await else {
+        return;
+    };
+
+    let hdr10 = ColorInfo {
+        matrix: ColorMatrix::Bt2020,
+        range: ColorRange::Full,
+        primaries: ColorPrimaries::Bt2020,
+        transfer: TransferFunction::Pq,
+        hdr: None,
+    };
+    // Neutral grey at PQ code value 130, about 100 cd/m²
+    let yuv = solid_frame_in(PixelFormat::Yuv420p, hdr10, &[&[130], &[128], &[128]]);
+    let expected = render_center(&mut renderer, &yuv);
+    assert!(expected[0] < 250, "grey clipped to white: {expected:?}");
+
+    let cases: [(PixelFormat, Pixels); 3] = [
+        (PixelFormat::RGBA, &[&[130, 130, 130, 255]]),
+        (PixelFormat::RGB24, &[&[130, 130, 130]]),
+        (PixelFormat::BGRA, &[&[130, 130, 130, 255]]),
+    ];
+    for (format, pixels) in cases {
+        let rgb = render_center(&mut renderer, &solid_frame_in(format, hdr10, pixels));
+        assert_close(format, rgb, expected);
+    }
+}